@@ -0,0 +1,561 @@
+//! Command-line tools for 168xB supplies
+
+use bk168xb::{
+    sim::{ConstantCurrent, Diode, Load, Open, Resistor, Short, VirtualSupply},
+    transcript::{self, Direction, Event, Record},
+    OutputState, SupplyVariant, BK1685B, BK1687B, BK1688B,
+};
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    process,
+    time::Duration,
+};
+
+const USAGE: &str = "\
+Usage: bk168xb <COMMAND> [OPTIONS]
+
+Commands:
+  emulate    Serve an emulated supply on a new pseudo-terminal
+  decode     Decode captured traffic into commands and responses
+
+Options for emulate:
+  --model MODEL          Supply to emulate: BK1685B, BK1687B (default), or
+                         BK1688B
+  --voltage VOLTS        Initial voltage setpoint
+  --current AMPS         Initial current setpoint
+  --voltage-limit VOLTS  Initial \"soft\" voltage limit
+  --current-limit AMPS   Initial \"soft\" current limit
+  --output on|off        Initial output state
+  --load LOAD            What's connected to the output: open (default),
+                         short, diode, <OHMS>ohm, or <AMPS>A
+  --link PATH            Also make the pseudo-terminal available at PATH,
+                         replacing any symlink already there
+
+Options for decode:
+  --model MODEL          Supply which was captured (default BK1687B)
+  --tx FILE              Raw bytes sent to the supply
+  --rx FILE              Raw bytes received from the supply
+  LOG                    A transcript interleaving both directions, or '-'
+                         for stdin (used unless --tx or --rx is given)
+
+decode exits with status 1 if the traffic contains protocol errors.";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    let mut args = env::args().skip(1);
+
+    let result = match args.next().as_deref() {
+        Some("emulate") => EmulateOptions::parse(args).and_then(emulate),
+        Some("decode") => DecodeOptions::parse(args).and_then(decode),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command '{}'", command).into()),
+        None => Err("no command given".into()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
+
+/// Configuration for an emulated supply.
+#[derive(Debug)]
+#[cfg_attr(not(all(unix, feature = "serialport")), allow(dead_code))]
+struct EmulateOptions {
+    supply: VirtualSupply,
+    link: Option<String>,
+}
+
+impl EmulateOptions {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut variant = BK1687B;
+        let mut voltage = None;
+        let mut current = None;
+        let mut voltage_limit = None;
+        let mut current_limit = None;
+        let mut output = None;
+        let mut load: Box<dyn Load> = Box::new(Open);
+        let mut link = None;
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", flag))?;
+
+            match flag.as_str() {
+                "--model" => variant = parse_model(&value)?,
+                "--voltage" => voltage = Some(parse_number(&value)?),
+                "--current" => current = Some(parse_number(&value)?),
+                "--voltage-limit" => {
+                    voltage_limit = Some(parse_number(&value)?)
+                }
+                "--current-limit" => {
+                    current_limit = Some(parse_number(&value)?)
+                }
+                "--output" => output = Some(parse_output(&value)?),
+                "--load" => load = parse_load(&value)?,
+                "--link" => link = Some(value),
+                _ => return Err(format!("unknown option '{}'", flag).into()),
+            }
+        }
+
+        let max_voltage = variant.nominal_max_voltage as f32;
+        let max_current = variant.nominal_max_current as f32;
+        for (flag, value, max) in [
+            ("--voltage", voltage, max_voltage),
+            ("--current", current, max_current),
+            ("--voltage-limit", voltage_limit, max_voltage),
+            ("--current-limit", current_limit, max_current),
+        ] {
+            if value.is_some_and(|value| value > max) {
+                return Err(format!(
+                    "{} is above the {}'s maximum of {}",
+                    flag, variant.model, max
+                )
+                .into());
+            }
+        }
+
+        // Setpoints can't exceed the soft limits, either.
+        for (flag, value, limit_flag, limit) in [
+            ("--voltage", voltage, "--voltage-limit", voltage_limit),
+            ("--current", current, "--current-limit", current_limit),
+        ] {
+            if let (Some(value), Some(limit)) = (value, limit) {
+                if value > limit {
+                    return Err(
+                        format!("{} is above {}", flag, limit_flag).into()
+                    );
+                }
+            }
+        }
+
+        let mut supply = VirtualSupply::new(variant);
+        let state = supply.state_mut();
+        state.settings.voltage = voltage.unwrap_or(state.settings.voltage);
+        state.settings.current = current.unwrap_or(state.settings.current);
+        state.voltage_limit = voltage_limit.unwrap_or(state.voltage_limit);
+        state.current_limit = current_limit.unwrap_or(state.current_limit);
+        state.output = output.unwrap_or(state.output);
+        supply.set_load(load);
+
+        Ok(EmulateOptions { supply, link })
+    }
+}
+
+/// Where to find captured traffic.
+#[derive(Debug)]
+struct DecodeOptions {
+    variant: &'static SupplyVariant,
+    log: Option<String>,
+    tx: Option<String>,
+    rx: Option<String>,
+}
+
+impl DecodeOptions {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut variant = BK1687B;
+        let mut log = None;
+        let mut tx = None;
+        let mut rx = None;
+
+        while let Some(arg) = args.next() {
+            if arg == "-" || !arg.starts_with('-') {
+                if log.replace(arg).is_some() {
+                    return Err("more than one transcript given".into());
+                }
+                continue;
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", arg))?;
+
+            match arg.as_str() {
+                "--model" => variant = parse_model(&value)?,
+                "--tx" => tx = Some(value),
+                "--rx" => rx = Some(value),
+                _ => return Err(format!("unknown option '{}'", arg).into()),
+            }
+        }
+
+        let separate = tx.is_some() || rx.is_some();
+        match (&log, separate) {
+            (Some(_), true) => {
+                Err("give either a transcript or --tx/--rx, not both".into())
+            }
+            (None, false) => Err("no traffic given".into()),
+            _ => Ok(DecodeOptions {
+                variant,
+                log,
+                tx,
+                rx,
+            }),
+        }
+    }
+}
+
+fn parse_model(name: &str) -> Result<&'static SupplyVariant> {
+    [BK1685B, BK1687B, BK1688B]
+        .iter()
+        .find(|variant| variant.model.eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| format!("unknown model '{}'", name).into())
+}
+
+fn parse_number(value: &str) -> Result<f32> {
+    match value.parse::<f32>() {
+        Ok(number) if number.is_finite() && number >= 0. => Ok(number),
+        _ => Err(format!("invalid quantity '{}'", value).into()),
+    }
+}
+
+fn parse_output(value: &str) -> Result<OutputState> {
+    match value {
+        "on" => Ok(OutputState::On),
+        "off" => Ok(OutputState::Off),
+        _ => Err(format!("invalid output state '{}'", value).into()),
+    }
+}
+
+fn parse_load(value: &str) -> Result<Box<dyn Load>> {
+    let load: Box<dyn Load> = match value {
+        "open" => Box::new(Open),
+        "short" => Box::new(Short),
+        "diode" => Box::new(Diode::silicon()),
+        _ => {
+            if let Some(ohms) = value.strip_suffix("ohm") {
+                Box::new(Resistor(parse_number(ohms)?))
+            } else if let Some(amps) = value.strip_suffix('A') {
+                Box::new(ConstantCurrent(parse_number(amps)?))
+            } else {
+                return Err(format!("invalid load '{}'", value).into());
+            }
+        }
+    };
+
+    Ok(load)
+}
+
+fn decode(options: DecodeOptions) -> Result<()> {
+    let records = match options.log.as_deref() {
+        Some("-") => transcript::read(io::stdin().lock())?,
+        Some(path) => {
+            transcript::read(io::BufReader::new(fs::File::open(path)?))?
+        }
+        None => {
+            // Separate captures carry no timing, so all transmitted bytes are
+            // treated as preceding all received bytes.
+            let mut records = Vec::new();
+            for (direction, path) in
+                [(Direction::Tx, &options.tx), (Direction::Rx, &options.rx)]
+            {
+                if let Some(path) = path {
+                    let mut bytes = Vec::new();
+                    fs::File::open(path)?.read_to_end(&mut bytes)?;
+                    records.push(Record {
+                        time: Duration::ZERO,
+                        direction,
+                        bytes,
+                    });
+                }
+            }
+            records
+        }
+    };
+
+    let events = transcript::decode(&records, options.variant);
+    for event in &events {
+        println!("{}", event);
+    }
+
+    let errors = events.iter().filter(|e| Event::is_error(e)).count();
+    if errors > 0 {
+        eprintln!("{} protocol error(s)", errors);
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "serialport"))]
+fn emulate(options: EmulateOptions) -> Result<()> {
+    use serialport::{SerialPort, TTYPort};
+
+    let EmulateOptions { mut supply, link } = options;
+
+    // Hold on to the slave end, so that the master doesn't see a hangup
+    // between clients.
+    let (mut master, slave) = TTYPort::pair()?;
+    master.set_timeout(Duration::from_secs(3600))?;
+    let path = slave.name().ok_or("pseudo-terminal has no name")?;
+
+    if let Some(link) = &link {
+        replace_link(&path, link)?;
+    }
+
+    println!("{}", path);
+    io::stdout().flush()?;
+
+    serve(&mut supply, &mut master)
+}
+
+/// Answer commands arriving on `port` until it reaches end-of-file.
+#[cfg_attr(not(all(unix, feature = "serialport")), allow(dead_code))]
+fn serve<P: Read + Write>(
+    supply: &mut VirtualSupply,
+    port: &mut P,
+) -> Result<()> {
+    let mut buf = [0; 64];
+    loop {
+        let count = match port.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(count) => count,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        supply.write_all(&buf[..count])?;
+        io::copy(supply, port)?;
+        port.flush()?;
+    }
+}
+
+/// Make `target` available at `link`, replacing any symlink already there.
+///
+/// Anything else at `link` is left alone, in case it was given by mistake.
+#[cfg(unix)]
+#[cfg_attr(not(feature = "serialport"), allow(dead_code))]
+fn replace_link(target: &str, link: &str) -> Result<()> {
+    match fs::symlink_metadata(link) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::remove_file(link)?,
+        Ok(_) => {
+            return Err(format!("'{}' exists, and isn't a symlink", link).into())
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    std::os::unix::fs::symlink(target, link)?;
+
+    Ok(())
+}
+
+#[cfg(not(all(unix, feature = "serialport")))]
+fn emulate(_options: EmulateOptions) -> Result<()> {
+    Err("emulation requires pseudo-terminals and the serialport feature".into())
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, matchers::*,
+    };
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// A client which sends a few bytes at a time.
+    struct Client {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Client {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(3);
+            self.input.read(&mut buf[..len])
+        }
+    }
+
+    impl Write for Client {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    test parses_emulate_options() {
+        let options = EmulateOptions::parse(args(&[
+            "--model", "bk1688b",
+            "--voltage", "5",
+            "--current", "1.5",
+            "--voltage-limit", "12",
+            "--current-limit", "2",
+            "--output", "on",
+            "--load", "10ohm",
+            "--link", "/tmp/supply",
+        ]))
+        .unwrap();
+
+        let supply = &options.supply;
+        let state = supply.state();
+        expect_that!(&supply.variant(), eq(BK1688B));
+        expect_that!(&state.settings.voltage, eq(5.));
+        expect_that!(&state.settings.current, eq(1.5));
+        expect_that!(&state.voltage_limit, eq(12.));
+        expect_that!(&state.current_limit, eq(2.));
+        expect_that!(&state.output, eq(OutputState::On));
+        expect_that!(&supply.load().current(5.), eq(0.5));
+        expect_that!(&options.link.as_deref(), eq(Some("/tmp/supply")));
+    }
+
+    test defaults_emulate_options() {
+        let options = EmulateOptions::parse(args(&[])).unwrap();
+
+        expect_that!(&options.supply.variant(), eq(BK1687B));
+        expect_that!(&options.supply.state().output, eq(OutputState::Off));
+        expect_that!(&options.link, eq(None));
+    }
+
+    test rejects_bad_emulate_options() {
+        for bad in &[
+            &["--model"][..],
+            &["--model", "BK1234B"],
+            &["--voltage", "-1"],
+            &["--current", "lots"],
+            &["--voltage", "36.1"],
+            &["--current", "10.5"],
+            &["--voltage-limit", "40"],
+            &["--current-limit", "11"],
+            &["--model", "BK1685B", "--current", "6"],
+            &["--voltage", "30", "--model", "BK1688B"],
+            &["--voltage", "12", "--voltage-limit", "10"],
+            &["--current", "2", "--current-limit", "1"],
+            &["--output", "maybe"],
+            &["--load", "capacitor"],
+            &["--frobnicate", "1"],
+        ] {
+            assert_that!(&EmulateOptions::parse(args(bad)).is_err(), eq(true));
+        }
+    }
+
+    test parses_decode_options() {
+        let options = DecodeOptions::parse(args(&[
+            "--model", "BK1685B",
+            "capture.log",
+        ]))
+        .unwrap();
+        expect_that!(&options.variant, eq(BK1685B));
+        expect_that!(&options.log.as_deref(), eq(Some("capture.log")));
+
+        let options =
+            DecodeOptions::parse(args(&["--tx", "tx.bin", "--rx", "rx.bin"]))
+                .unwrap();
+        expect_that!(&options.variant, eq(BK1687B));
+        expect_that!(&options.tx.as_deref(), eq(Some("tx.bin")));
+        expect_that!(&options.rx.as_deref(), eq(Some("rx.bin")));
+    }
+
+    test rejects_bad_decode_options() {
+        for bad in &[
+            &[][..],
+            &["a.log", "b.log"],
+            &["a.log", "--tx", "tx.bin"],
+            &["--rx"],
+            &["--frobnicate", "1"],
+        ] {
+            assert_that!(&DecodeOptions::parse(args(bad)).is_err(), eq(true));
+        }
+    }
+
+    test serves_commands() {
+        let mut supply = VirtualSupply::new(BK1687B);
+        let mut client = Client {
+            input: io::Cursor::new(b"VOLT050\rGETS\rSOUT0\r".to_vec()),
+            output: Vec::new(),
+        };
+
+        serve(&mut supply, &mut client).unwrap();
+
+        let output = String::from_utf8(client.output).unwrap();
+        expect_that!(&output.as_str(), eq("OK\r050000\rOK\rOK\r"));
+        expect_that!(&supply.state().output, eq(OutputState::On));
+    }
+}
+
+#[cfg(all(test, unix))]
+galvanic_test::test_suite! {
+    name link_test;
+
+    use super::*;
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, matchers::*,
+    };
+    use std::path::PathBuf;
+
+    /// A directory which is removed when dropped.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("bk168xb-{}-{}", name, process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            Scratch(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    test creates_link() {
+        let scratch = Scratch::new("creates-link");
+        let link = scratch.0.join("new");
+        let link = link.to_str().unwrap();
+
+        replace_link("/dev/null", link).unwrap();
+
+        let target = fs::read_link(link).unwrap();
+        expect_that!(&target, eq(PathBuf::from("/dev/null")));
+    }
+
+    test replaces_link() {
+        let scratch = Scratch::new("replaces-link");
+        let link = scratch.0.join("old");
+        std::os::unix::fs::symlink("/dev/zero", &link).unwrap();
+        let link = link.to_str().unwrap();
+
+        replace_link("/dev/null", link).unwrap();
+
+        let target = fs::read_link(link).unwrap();
+        expect_that!(&target, eq(PathBuf::from("/dev/null")));
+    }
+
+    test refuses_to_replace_file() {
+        let scratch = Scratch::new("refuses-file");
+        let file = scratch.0.join("file");
+        fs::write(&file, "precious").unwrap();
+
+        let result = replace_link("/dev/null", file.to_str().unwrap());
+
+        assert_that!(&result.is_err(), eq(true));
+        let contents = fs::read_to_string(&file).unwrap();
+        expect_that!(&contents.as_str(), eq("precious"));
+    }
+}
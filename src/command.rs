@@ -1,6 +1,8 @@
 //! Power supply command set
 
+mod any;
 mod core;
+mod decoder;
 mod error;
 mod get;
 mod select_preset;
@@ -15,7 +17,7 @@ mod set_voltage_limit;
 pub(crate) mod test_util;
 
 pub use self::{
-    core::*, error::*, get::*, select_preset::*, set_current::*,
-    set_current_limit::*, set_output::*, set_presets::*, set_voltage::*,
-    set_voltage_limit::*,
+    any::*, core::*, decoder::*, error::*, get::*, select_preset::*,
+    set_current::*, set_current_limit::*, set_output::*, set_presets::*,
+    set_voltage::*, set_voltage_limit::*,
 };
@@ -0,0 +1,363 @@
+//! A command of any type.
+
+use crate::{
+    command::{
+        Command, CommandSink, Error, GetCapabilities, GetCurrentLimit,
+        GetPresets, GetSettings, GetStatus, GetVoltageLimit, Result,
+        SelectPreset, SetCurrent, SetCurrentLimit, SetOutput, SetPresets,
+        SetVoltage, SetVoltageLimit,
+    },
+    SupplyVariant,
+};
+
+use std::io;
+
+/// Any one of the supply's commands.
+///
+/// This is what a supply sees on the wire: the command's type is only known
+/// once its function has been read.
+///
+/// ```
+/// use bk168xb::{
+///     command::{AnyCommand, SetVoltage},
+///     BK1687B,
+/// };
+///
+/// # fn main() -> bk168xb::command::Result<()> {
+/// let command = AnyCommand::decode(b"VOLT123\r", BK1687B)?;
+/// assert_eq!(command, AnyCommand::SetVoltage(SetVoltage(12.3)));
+///
+/// let mut raw = Vec::new();
+/// command.encode(&mut raw, BK1687B)?;
+/// assert_eq!(raw, b"VOLT123\r");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+pub enum AnyCommand {
+    /// See [`GetSettings`].
+    GetSettings(GetSettings),
+
+    /// See [`GetStatus`].
+    GetStatus(GetStatus),
+
+    /// See [`GetVoltageLimit`].
+    GetVoltageLimit(GetVoltageLimit),
+
+    /// See [`GetCurrentLimit`].
+    GetCurrentLimit(GetCurrentLimit),
+
+    /// See [`GetCapabilities`].
+    GetCapabilities(GetCapabilities),
+
+    /// See [`GetPresets`].
+    GetPresets(GetPresets),
+
+    /// See [`SetVoltage`].
+    SetVoltage(SetVoltage),
+
+    /// See [`SetCurrent`].
+    SetCurrent(SetCurrent),
+
+    /// See [`SetVoltageLimit`].
+    SetVoltageLimit(SetVoltageLimit),
+
+    /// See [`SetCurrentLimit`].
+    SetCurrentLimit(SetCurrentLimit),
+
+    /// See [`SetOutput`].
+    SetOutput(SetOutput),
+
+    /// See [`SetPresets`].
+    SetPresets(SetPresets),
+
+    /// See [`SelectPreset`].
+    SelectPreset(SelectPreset),
+}
+
+impl AnyCommand {
+    /// Decode a single command, including its terminating carriage-return.
+    ///
+    /// # Errors
+    ///
+    /// - [`UnknownFunction`](Error::UnknownFunction): The command's function
+    ///   doesn't correspond to any known command
+    /// - [`MalformedCommand`](Error::MalformedCommand): The command is
+    ///   unterminated, or its arguments don't match its function's format
+    pub fn decode(raw: &[u8], variant: &SupplyVariant) -> Result<Self> {
+        let raw = match raw.split_last() {
+            Some((b'\r', raw)) => raw,
+            _ => return Err(Error::MalformedCommand),
+        };
+
+        if raw.len() < 4 {
+            return Err(Error::MalformedCommand);
+        }
+        let (function, args) = raw.split_at(4);
+
+        let command = match function {
+            b"GETS" => GetSettings::parse_args(args, variant)?.into(),
+            b"GETD" => GetStatus::parse_args(args, variant)?.into(),
+            b"GOVP" => GetVoltageLimit::parse_args(args, variant)?.into(),
+            b"GOCP" => GetCurrentLimit::parse_args(args, variant)?.into(),
+            b"GMAX" => GetCapabilities::parse_args(args, variant)?.into(),
+            b"GETM" => GetPresets::parse_args(args, variant)?.into(),
+            b"VOLT" => SetVoltage::parse_args(args, variant)?.into(),
+            b"CURR" => SetCurrent::parse_args(args, variant)?.into(),
+            b"SOVP" => SetVoltageLimit::parse_args(args, variant)?.into(),
+            b"SOCP" => SetCurrentLimit::parse_args(args, variant)?.into(),
+            b"SOUT" => SetOutput::parse_args(args, variant)?.into(),
+            b"PROM" => SetPresets::parse_args(args, variant)?.into(),
+            b"RUNM" => SelectPreset::parse_args(args, variant)?.into(),
+            _ => {
+                let function = String::from_utf8_lossy(function).into_owned();
+
+                return Err(Error::UnknownFunction(function));
+            }
+        };
+
+        Ok(command)
+    }
+
+    /// The argument-less command with the given function.
+    ///
+    /// This covers every query (`"GETD"`, `"GETM"`, and so on), for callers
+    /// which only decide at runtime which to send.
+    ///
+    /// # Errors
+    ///
+    /// - [`UnknownFunction`](Error::UnknownFunction): `function` doesn't
+    ///   correspond to any known command
+    /// - [`MalformedCommand`](Error::MalformedCommand): The command requires
+    ///   arguments
+    pub fn from_function(
+        function: &str,
+        variant: &SupplyVariant,
+    ) -> Result<Self> {
+        if function.len() != 4 {
+            return Err(Error::UnknownFunction(function.to_owned()));
+        }
+
+        let mut raw = function.as_bytes().to_vec();
+        raw.push(b'\r');
+
+        Self::decode(&raw, variant)
+    }
+
+    /// Write the complete command, including its terminating carriage-return.
+    pub fn encode<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        match self {
+            AnyCommand::GetSettings(c) => sink.send_command(c, variant),
+            AnyCommand::GetStatus(c) => sink.send_command(c, variant),
+            AnyCommand::GetVoltageLimit(c) => sink.send_command(c, variant),
+            AnyCommand::GetCurrentLimit(c) => sink.send_command(c, variant),
+            AnyCommand::GetCapabilities(c) => sink.send_command(c, variant),
+            AnyCommand::GetPresets(c) => sink.send_command(c, variant),
+            AnyCommand::SetVoltage(c) => sink.send_command(c, variant),
+            AnyCommand::SetCurrent(c) => sink.send_command(c, variant),
+            AnyCommand::SetVoltageLimit(c) => sink.send_command(c, variant),
+            AnyCommand::SetCurrentLimit(c) => sink.send_command(c, variant),
+            AnyCommand::SetOutput(c) => sink.send_command(c, variant),
+            AnyCommand::SetPresets(c) => sink.send_command(c, variant),
+            AnyCommand::SelectPreset(c) => sink.send_command(c, variant),
+        }
+    }
+
+    /// The command's four-character function.
+    pub fn function(&self) -> &'static str {
+        match self {
+            AnyCommand::GetSettings(_) => GetSettings::FUNCTION,
+            AnyCommand::GetStatus(_) => GetStatus::FUNCTION,
+            AnyCommand::GetVoltageLimit(_) => GetVoltageLimit::FUNCTION,
+            AnyCommand::GetCurrentLimit(_) => GetCurrentLimit::FUNCTION,
+            AnyCommand::GetCapabilities(_) => GetCapabilities::FUNCTION,
+            AnyCommand::GetPresets(_) => GetPresets::FUNCTION,
+            AnyCommand::SetVoltage(_) => SetVoltage::FUNCTION,
+            AnyCommand::SetCurrent(_) => SetCurrent::FUNCTION,
+            AnyCommand::SetVoltageLimit(_) => SetVoltageLimit::FUNCTION,
+            AnyCommand::SetCurrentLimit(_) => SetCurrentLimit::FUNCTION,
+            AnyCommand::SetOutput(_) => SetOutput::FUNCTION,
+            AnyCommand::SetPresets(_) => SetPresets::FUNCTION,
+            AnyCommand::SelectPreset(_) => SelectPreset::FUNCTION,
+        }
+    }
+}
+
+impl From<GetSettings> for AnyCommand {
+    fn from(c: GetSettings) -> Self {
+        AnyCommand::GetSettings(c)
+    }
+}
+
+impl From<GetStatus> for AnyCommand {
+    fn from(c: GetStatus) -> Self {
+        AnyCommand::GetStatus(c)
+    }
+}
+
+impl From<GetVoltageLimit> for AnyCommand {
+    fn from(c: GetVoltageLimit) -> Self {
+        AnyCommand::GetVoltageLimit(c)
+    }
+}
+
+impl From<GetCurrentLimit> for AnyCommand {
+    fn from(c: GetCurrentLimit) -> Self {
+        AnyCommand::GetCurrentLimit(c)
+    }
+}
+
+impl From<GetCapabilities> for AnyCommand {
+    fn from(c: GetCapabilities) -> Self {
+        AnyCommand::GetCapabilities(c)
+    }
+}
+
+impl From<GetPresets> for AnyCommand {
+    fn from(c: GetPresets) -> Self {
+        AnyCommand::GetPresets(c)
+    }
+}
+
+impl From<SetVoltage> for AnyCommand {
+    fn from(c: SetVoltage) -> Self {
+        AnyCommand::SetVoltage(c)
+    }
+}
+
+impl From<SetCurrent> for AnyCommand {
+    fn from(c: SetCurrent) -> Self {
+        AnyCommand::SetCurrent(c)
+    }
+}
+
+impl From<SetVoltageLimit> for AnyCommand {
+    fn from(c: SetVoltageLimit) -> Self {
+        AnyCommand::SetVoltageLimit(c)
+    }
+}
+
+impl From<SetCurrentLimit> for AnyCommand {
+    fn from(c: SetCurrentLimit) -> Self {
+        AnyCommand::SetCurrentLimit(c)
+    }
+}
+
+impl From<SetOutput> for AnyCommand {
+    fn from(c: SetOutput) -> Self {
+        AnyCommand::SetOutput(c)
+    }
+}
+
+impl From<SetPresets> for AnyCommand {
+    fn from(c: SetPresets) -> Self {
+        AnyCommand::SetPresets(c)
+    }
+}
+
+impl From<SelectPreset> for AnyCommand {
+    fn from(c: SelectPreset) -> Self {
+        AnyCommand::SelectPreset(c)
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        test_util::{any_psu, high_voltage_psu},
+        OperatingPoint, OutputState, PresetIndex,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    fn all_commands() -> Vec<AnyCommand> {
+        let point = OperatingPoint {
+            voltage: 1.2,
+            current: 3.4,
+        };
+
+        vec![
+            GetSettings.into(),
+            GetStatus.into(),
+            GetVoltageLimit.into(),
+            GetCurrentLimit.into(),
+            GetCapabilities.into(),
+            GetPresets.into(),
+            SetVoltage(12.3).into(),
+            SetCurrent(4.5).into(),
+            SetVoltageLimit(15.).into(),
+            SetCurrentLimit(2.5).into(),
+            SetOutput(OutputState::On).into(),
+            SetPresets(point, point, point).into(),
+            SelectPreset(PresetIndex::Three).into(),
+        ]
+    }
+
+    test round_trips_all_commands(any_psu) {
+        let variant = any_psu.val;
+
+        for command in all_commands() {
+            let mut raw = Vec::new();
+            command.encode(&mut raw, variant).unwrap();
+
+            expect_that!(&&raw[..4], eq(command.function().as_bytes()));
+            expect_that!(
+                &AnyCommand::decode(&raw, variant).unwrap(),
+                eq(command)
+            );
+        }
+    }
+
+    test decodes_per_variant(high_voltage_psu) {
+        let command = AnyCommand::decode(b"CURR123\r", high_voltage_psu.val);
+
+        expect_that!(
+            &command.unwrap(),
+            eq(AnyCommand::SetCurrent(SetCurrent(1.23)))
+        );
+    }
+
+    test rejects_unknown_function(any_psu) {
+        let err = AnyCommand::decode(b"ABCD\r", any_psu.val).unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::UnknownFunction [
+            eq(String::from("ABCD"))
+        ]));
+    }
+
+    test builds_from_function(any_psu) {
+        let variant = any_psu.val;
+
+        let status = AnyCommand::from_function("GETD", variant);
+        expect_that!(&status.unwrap(), eq(AnyCommand::GetStatus(GetStatus)));
+
+        let err = AnyCommand::from_function("GETDX", variant).unwrap_err();
+        assert_that!(&err, has_structure!(Error::UnknownFunction [
+            eq(String::from("GETDX"))
+        ]));
+
+        let err = AnyCommand::from_function("VOLT", variant).unwrap_err();
+        assert_that!(&err, is_variant!(Error::MalformedCommand));
+    }
+
+    test rejects_malformed(any_psu) {
+        let variant = any_psu.val;
+
+        for raw in &[&b""[..], b"\r", b"GET\r", b"GETD", b"GETD1\r", b"VOLT1\r"] {
+            let err = AnyCommand::decode(raw, variant).unwrap_err();
+            assert_that!(&err, is_variant!(Error::MalformedCommand));
+        }
+    }
+}
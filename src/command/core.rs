@@ -1,15 +1,42 @@
-use crate::{command::Result, SupplyVariant};
+use crate::{
+    command::{Error, Result},
+    response::Response,
+    SupplyVariant,
+};
 
 use std::io;
 
 /// A PSU command.
+///
+/// Every command is answered by exactly one kind of response. Encoding that
+/// pairing in the type system means that reading the wrong reply format is a
+/// compile-time error rather than a runtime parse failure:
+///
+/// ```compile_fail
+/// use bk168xb::{command::GetStatus, response::Settings, Supply, BK1687B};
+/// use std::io::Cursor;
+///
+/// let mut supply = Supply::new(Cursor::new(Vec::new()), BK1687B);
+/// let settings: Settings = supply.transact(&GetStatus).unwrap();
+/// ```
 pub trait Command {
+    /// The response the supply sends after receiving this command.
+    type Response: Response;
+
     /// Function-discrimination part of a command.
     ///
     /// Each command starts with a four-character "function." This describes
     /// what operation is being performed.
     const FUNCTION: &'static str;
 
+    /// Whether the command can be repeated blindly if its response is lost.
+    ///
+    /// This holds for queries, which don't change the supply's state, and for
+    /// [`SetOutput`](crate::command::SetOutput), which sets the output state
+    /// outright. Lost responses to other commands are handled through
+    /// [`ReadBack`], if at all.
+    const IDEMPOTENT: bool = false;
+
     /// Write a command's arguments to the specified sink.
     ///
     /// The default implementation of this function serializes no arguments.
@@ -27,6 +54,73 @@ pub trait Command {
 
         Ok(())
     }
+
+    /// Parse a command's arguments, as written by
+    /// [`serialize_args()`](Command::serialize_args).
+    ///
+    /// # Arguments
+    ///
+    /// - `raw`: Everything between the function and the terminating
+    ///   carriage-return
+    /// - `variant`: Provides information about per-supply serialization quirks
+    fn parse_args(raw: &[u8], variant: &SupplyVariant) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+/// A state-changing command whose effect can be read back from the supply.
+///
+/// If such a command's acknowledgement is lost, the supply can be queried to
+/// find out whether it took effect before sending it again. See
+/// [`Supply::transact_verified()`](crate::Supply::transact_verified).
+pub trait ReadBack: Command<Response = ()> {
+    /// The query which reads back this command's effect.
+    type Query: Command;
+
+    /// An instance of the query.
+    const QUERY: Self::Query;
+
+    /// Whether `state`, the supply's response to [`QUERY`], shows that this
+    /// command has taken effect.
+    ///
+    /// Values are compared as they're encoded on the wire, so a command
+    /// which sets a value with more precision than the supply supports is
+    /// still considered applied.
+    ///
+    /// [`QUERY`]: ReadBack::QUERY
+    fn is_applied(
+        &self,
+        state: &<Self::Query as Command>::Response,
+        variant: &SupplyVariant,
+    ) -> bool;
+}
+
+/// Whether two commands are encoded identically.
+///
+/// Commands which can't be encoded at all are never considered the same.
+pub(crate) fn same_encoding<C: Command>(
+    a: &C,
+    b: &C,
+    variant: &SupplyVariant,
+) -> bool {
+    let encode = |command: &C| {
+        let mut raw = Vec::new();
+        raw.send_command(command, variant).map(|_| raw)
+    };
+
+    match (encode(a), encode(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Verify that a command has no arguments.
+pub(crate) fn verify_no_args(raw: &[u8]) -> Result<()> {
+    if raw.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MalformedCommand)
+    }
 }
 
 /// A target for command serialization.
@@ -0,0 +1,229 @@
+use crate::{
+    command::{AnyCommand, Error, Result},
+    SupplyVariant,
+};
+
+/// The largest command a [`Decoder`] can hold.
+///
+/// This comfortably exceeds the size of any command the supplies accept.
+pub const MAX_COMMAND_BYTES: usize = 32;
+
+/// An incremental, push-based command decoder.
+///
+/// This is the command-side counterpart of
+/// [`Parser`](crate::response::Parser): bytes are pushed in as they arrive,
+/// and a command is produced whenever a complete one has been received.
+///
+/// ```
+/// use bk168xb::{
+///     command::{AnyCommand, Decoder, GetStatus, SetVoltage},
+///     BK1687B,
+/// };
+///
+/// # fn main() -> bk168xb::command::Result<()> {
+/// let mut decoder = Decoder::new();
+///
+/// let mut input = "VOLT1".as_bytes();
+/// assert_eq!(decoder.feed(&mut input, BK1687B)?, None);
+///
+/// let mut input = "23\rGETD\r".as_bytes();
+/// let decoded = decoder.feed(&mut input, BK1687B)?;
+/// assert_eq!(decoded, Some(AnyCommand::SetVoltage(SetVoltage(12.3))));
+///
+/// let decoded = decoder.feed(&mut input, BK1687B)?;
+/// assert_eq!(decoded, Some(AnyCommand::GetStatus(GetStatus)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Decoder {
+    buf: [u8; MAX_COMMAND_BYTES],
+    len: usize,
+    overflowed: bool,
+}
+
+impl Decoder {
+    /// Create a decoder, ready for the start of a command.
+    pub fn new() -> Self {
+        Decoder {
+            buf: [0; MAX_COMMAND_BYTES],
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Feed bytes to the decoder.
+    ///
+    /// Bytes are consumed from the front of `input` only until the end of a
+    /// command -- on return, `input` holds whatever was left over. This is
+    /// true whether that command decoded successfully or not, so after an
+    /// error the remaining input can be fed to the decoder again.
+    ///
+    /// Returns `None` if all input was consumed without completing a command.
+    ///
+    /// # Arguments
+    ///
+    /// - `input`: Bytes sent to the supply
+    /// - `variant`: Provides information about per-supply parsing quirks
+    pub fn feed(
+        &mut self,
+        input: &mut &[u8],
+        variant: &SupplyVariant,
+    ) -> Result<Option<AnyCommand>> {
+        while let Some((&byte, rest)) = input.split_first() {
+            *input = rest;
+            self.push(byte);
+
+            if byte == b'\r' {
+                let overflowed = self.overflowed;
+                let len = self.len;
+                self.reset();
+
+                if overflowed {
+                    return Err(Error::MalformedCommand);
+                }
+
+                return AnyCommand::decode(&self.buf[..len], variant).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Discard any partially-received command.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    /// Whether part of a command has been received.
+    pub fn in_progress(&self) -> bool {
+        self.len != 0
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.len == self.buf.len() {
+            // This can't be a valid command. Only the terminator matters from
+            // here on.
+            self.len = 0;
+            self.overflowed = true;
+        }
+
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command::{GetSettings, SetOutput, SetPresets},
+        test_util::{any_psu, low_voltage_psu},
+        OperatingPoint, OutputState,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test decodes_byte_at_a_time(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let raw = "PROM050010125030150005\r".as_bytes();
+        let mut decoder = Decoder::new();
+
+        let (last, most) = raw.split_last().unwrap();
+        for byte in most {
+            let mut input = std::slice::from_ref(byte);
+            let decoded = decoder.feed(&mut input, variant).unwrap();
+            expect_that!(&decoded, eq(None));
+            expect_that!(&input.is_empty(), eq(true));
+        }
+
+        let mut input = std::slice::from_ref(last);
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(Some(AnyCommand::SetPresets(SetPresets(
+            OperatingPoint { voltage: 5.0, current: 1.0 },
+            OperatingPoint { voltage: 12.5, current: 3.0 },
+            OperatingPoint { voltage: 15.0, current: 0.5 },
+        )))));
+        expect_that!(&decoder.in_progress(), eq(false));
+    }
+
+    test leaves_following_bytes(any_psu) {
+        let variant = any_psu.val;
+        let mut input = "SOUT0\rGETS\r".as_bytes();
+        let mut decoder = Decoder::new();
+
+        expect_that!(
+            &decoder.feed(&mut input, variant).unwrap(),
+            eq(Some(AnyCommand::SetOutput(SetOutput(OutputState::On))))
+        );
+        expect_that!(&input, eq("GETS\r".as_bytes()));
+
+        expect_that!(
+            &decoder.feed(&mut input, variant).unwrap(),
+            eq(Some(AnyCommand::GetSettings(GetSettings)))
+        );
+        expect_that!(&input.is_empty(), eq(true));
+    }
+
+    test error_consumes_whole_command(any_psu) {
+        let variant = any_psu.val;
+        let mut input = "WHAT\rGETS\r".as_bytes();
+        let mut decoder = Decoder::new();
+
+        let err = decoder.feed(&mut input, variant).unwrap_err();
+        assert_that!(&err, is_variant!(Error::UnknownFunction));
+        expect_that!(&input, eq("GETS\r".as_bytes()));
+
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(Some(AnyCommand::GetSettings(GetSettings))));
+    }
+
+    test rejects_oversized_command(any_psu) {
+        let variant = any_psu.val;
+        let garbage = "0".repeat(MAX_COMMAND_BYTES * 2);
+        let mut decoder = Decoder::new();
+
+        let mut input = garbage.as_bytes();
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(None));
+
+        let mut input = "GETS\rGETS\r".as_bytes();
+        let err = decoder.feed(&mut input, variant);
+        assert_that!(
+            &err,
+            has_structure!(Err [is_variant!(Error::MalformedCommand)])
+        );
+
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(Some(AnyCommand::GetSettings(GetSettings))));
+    }
+
+    test can_be_reset(any_psu) {
+        let variant = any_psu.val;
+        let mut decoder = Decoder::new();
+
+        let mut input = "garbage".as_bytes();
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(None));
+        expect_that!(&decoder.in_progress(), eq(true));
+
+        decoder.reset();
+
+        let mut input = "GETS\r".as_bytes();
+        let decoded = decoder.feed(&mut input, variant).unwrap();
+        expect_that!(&decoded, eq(Some(AnyCommand::GetSettings(GetSettings))));
+    }
+}
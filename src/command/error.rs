@@ -10,6 +10,14 @@ pub enum Error {
     #[error("unrepresentable value in command: {0}")]
     ValueUnrepresentable(f32),
 
+    /// The command's function wasn't recognized.
+    #[error("unknown command function: {0:?}")]
+    UnknownFunction(String),
+
+    /// The command's arguments didn't match the expected format.
+    #[error("malformed command")]
+    MalformedCommand,
+
     /// The sink returned an error while writing the command.
     #[error("failed to write command")]
     WriteFailure(#[from] io::Error),
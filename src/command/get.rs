@@ -1,5 +1,9 @@
-///! Commands for getting values from the supply.
-use crate::command::Command;
+//! Commands for getting values from the supply.
+use crate::{
+    command::{self, verify_no_args, Command},
+    response::{Capabilities, Current, Presets, Settings, Status, Voltage},
+    SupplyVariant,
+};
 
 /// Get the current output voltage and current
 ///
@@ -8,7 +12,20 @@ use crate::command::Command;
 pub struct GetSettings;
 
 impl Command for GetSettings {
+    type Response = Settings;
+
     const FUNCTION: &'static str = "GETS";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetSettings)
+    }
 }
 
 /// Get the current supply status, as displayed on the front panel.
@@ -22,7 +39,20 @@ impl Command for GetSettings {
 pub struct GetStatus;
 
 impl Command for GetStatus {
+    type Response = Status;
+
     const FUNCTION: &'static str = "GETD";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetStatus)
+    }
 }
 
 /// Get the maximum acceptable supply voltage.
@@ -32,7 +62,20 @@ impl Command for GetStatus {
 pub struct GetVoltageLimit;
 
 impl Command for GetVoltageLimit {
+    type Response = Voltage;
+
     const FUNCTION: &'static str = "GOVP";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetVoltageLimit)
+    }
 }
 
 /// Get the maximum acceptable supply current.
@@ -42,7 +85,20 @@ impl Command for GetVoltageLimit {
 pub struct GetCurrentLimit;
 
 impl Command for GetCurrentLimit {
+    type Response = Current;
+
     const FUNCTION: &'static str = "GOCP";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetCurrentLimit)
+    }
 }
 
 /// Determine the supply's absolute maximum voltage/current limits.
@@ -53,7 +109,20 @@ impl Command for GetCurrentLimit {
 pub struct GetCapabilities;
 
 impl Command for GetCapabilities {
+    type Response = Capabilities;
+
     const FUNCTION: &'static str = "GMAX";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetCapabilities)
+    }
 }
 
 /// Get a list of the pre-set operating points.
@@ -61,7 +130,20 @@ impl Command for GetCapabilities {
 pub struct GetPresets;
 
 impl Command for GetPresets {
+    type Response = Presets;
+
     const FUNCTION: &'static str = "GETM";
+
+    const IDEMPOTENT: bool = true;
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        verify_no_args(raw)?;
+
+        Ok(GetPresets)
+    }
 }
 
 #[cfg(test)]
@@ -99,4 +181,23 @@ test_suite! {
         assert_serializes_to(GetPresets, "GETM\r", any_psu.val);
     }
 
+    test parse_getters(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_parses_to("", GetSettings, variant);
+        let _e = expect_parses_to("", GetStatus, variant);
+        let _e = expect_parses_to("", GetVoltageLimit, variant);
+        let _e = expect_parses_to("", GetCurrentLimit, variant);
+        let _e = expect_parses_to("", GetCapabilities, variant);
+        let _e = expect_parses_to("", GetPresets, variant);
+    }
+
+    test cant_parse_arguments(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<GetSettings>("1", variant);
+        let _e = expect_cant_parse::<GetStatus>("1", variant);
+        let _e = expect_cant_parse::<GetVoltageLimit>("1", variant);
+        let _e = expect_cant_parse::<GetCurrentLimit>("1", variant);
+        let _e = expect_cant_parse::<GetCapabilities>("1", variant);
+        let _e = expect_cant_parse::<GetPresets>("1", variant);
+    }
 }
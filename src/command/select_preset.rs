@@ -12,6 +12,8 @@ use std::io;
 pub struct SelectPreset(pub PresetIndex);
 
 impl Command for SelectPreset {
+    type Response = ();
+
     const FUNCTION: &'static str = "RUNM";
 
     fn serialize_args<S: io::Write>(
@@ -26,6 +28,21 @@ impl Command for SelectPreset {
 
         fmt.serialize_arg(sink, self.0.arg_val() as f32)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: 0,
+            digits: 1,
+        };
+
+        let val = fmt.parse_arg(raw)? as usize;
+        PresetIndex::from_arg_val(val)
+            .map(SelectPreset)
+            .ok_or(command::Error::MalformedCommand)
+    }
 }
 
 #[cfg(test)]
@@ -37,7 +54,9 @@ test_suite! {
 
     use super::*;
 
-    use crate::command::test_util::expect_serializes_to;
+    use crate::command::test_util::{
+        expect_cant_parse, expect_parses_to, expect_serializes_to,
+    };
     use crate::PresetIndex;
     use crate::test_util::any_psu;
 
@@ -58,4 +77,19 @@ test_suite! {
             any_psu.val,
         );
     }
+
+    test parse_select_preset(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_parses_to("0", SelectPreset(PresetIndex::One), variant);
+        let _e = expect_parses_to("1", SelectPreset(PresetIndex::Two), variant);
+        let _e =
+            expect_parses_to("2", SelectPreset(PresetIndex::Three), variant);
+    }
+
+    test cant_parse_malformed(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<SelectPreset>("", variant);
+        let _e = expect_cant_parse::<SelectPreset>("3", variant);
+        let _e = expect_cant_parse::<SelectPreset>("x", variant);
+    }
 }
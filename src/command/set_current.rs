@@ -1,8 +1,8 @@
 //! Command for setting supply operating current
 
 use crate::{
-    command::{self, Command},
-    response::Current,
+    command::{self, same_encoding, Command, GetSettings, ReadBack},
+    response::{Current, Settings},
     ArgFormat, SupplyVariant,
 };
 
@@ -13,6 +13,8 @@ use std::io;
 pub struct SetCurrent(pub f32);
 
 impl Command for SetCurrent {
+    type Response = ();
+
     const FUNCTION: &'static str = "CURR";
 
     fn serialize_args<S: io::Write>(
@@ -27,6 +29,28 @@ impl Command for SetCurrent {
 
         fmt.serialize_arg(sink, self.0)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: 3,
+        };
+
+        fmt.parse_arg(raw).map(SetCurrent)
+    }
+}
+
+impl ReadBack for SetCurrent {
+    type Query = GetSettings;
+
+    const QUERY: GetSettings = GetSettings;
+
+    fn is_applied(&self, state: &Settings, variant: &SupplyVariant) -> bool {
+        same_encoding(self, &SetCurrent(state.current), variant)
+    }
 }
 
 impl From<Current> for SetCurrent {
@@ -51,7 +75,10 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::{assert_cant_serialize, expect_serializes_to},
+        command::test_util::{
+            assert_cant_serialize, expect_cant_parse, expect_parses_to,
+            expect_serializes_to,
+        },
         test_util::{
             high_voltage_psu, invalid_current_high_voltage,
             invalid_current_low_voltage, low_voltage_psu,
@@ -100,4 +127,26 @@ test_suite! {
             high_voltage_psu.val
         );
     }
+
+    test parse_for_low_v_psu(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let _e = expect_parses_to("123", SetCurrent(12.3), variant);
+        let _e = expect_parses_to("000", SetCurrent(0.), variant);
+        let _e = expect_parses_to("999", SetCurrent(99.9), variant);
+    }
+
+    test parse_for_high_v_psu(high_voltage_psu) {
+        let variant = high_voltage_psu.val;
+        let _e = expect_parses_to("123", SetCurrent(1.23), variant);
+        let _e = expect_parses_to("000", SetCurrent(0.), variant);
+        let _e = expect_parses_to("999", SetCurrent(9.99), variant);
+    }
+
+    test cant_parse_malformed(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let _e = expect_cant_parse::<SetCurrent>("", variant);
+        let _e = expect_cant_parse::<SetCurrent>("12", variant);
+        let _e = expect_cant_parse::<SetCurrent>("1234", variant);
+        let _e = expect_cant_parse::<SetCurrent>("-12", variant);
+    }
 }
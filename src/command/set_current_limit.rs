@@ -1,6 +1,6 @@
-///! Command for setting a "soft" current limit.
+//! Command for setting a "soft" current limit.
 use crate::{
-    command::{self, Command},
+    command::{self, same_encoding, Command, GetCurrentLimit, ReadBack},
     response::Current,
     ArgFormat, SupplyVariant,
 };
@@ -15,6 +15,8 @@ use std::io;
 pub struct SetCurrentLimit(pub f32);
 
 impl Command for SetCurrentLimit {
+    type Response = ();
+
     const FUNCTION: &'static str = "SOCP";
 
     fn serialize_args<S: io::Write>(
@@ -29,6 +31,28 @@ impl Command for SetCurrentLimit {
 
         fmt.serialize_arg(sink, self.0)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: 3,
+        };
+
+        fmt.parse_arg(raw).map(SetCurrentLimit)
+    }
+}
+
+impl ReadBack for SetCurrentLimit {
+    type Query = GetCurrentLimit;
+
+    const QUERY: GetCurrentLimit = GetCurrentLimit;
+
+    fn is_applied(&self, state: &Current, variant: &SupplyVariant) -> bool {
+        same_encoding(self, &SetCurrentLimit(state.0), variant)
+    }
 }
 
 impl From<Current> for SetCurrentLimit {
@@ -53,7 +77,10 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::{assert_cant_serialize, expect_serializes_to},
+        command::test_util::{
+            assert_cant_serialize, expect_cant_parse, expect_parses_to,
+            expect_serializes_to,
+        },
         test_util::{
             high_voltage_psu, invalid_current_high_voltage,
             invalid_current_low_voltage, low_voltage_psu,
@@ -101,4 +128,26 @@ test_suite! {
             high_voltage_psu.val
         );
     }
+
+    test parse_for_low_v_psu(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let _e = expect_parses_to("123", SetCurrentLimit(12.3), variant);
+        let _e = expect_parses_to("000", SetCurrentLimit(0.), variant);
+        let _e = expect_parses_to("999", SetCurrentLimit(99.9), variant);
+    }
+
+    test parse_for_high_v_psu(high_voltage_psu) {
+        let variant = high_voltage_psu.val;
+        let _e = expect_parses_to("123", SetCurrentLimit(1.23), variant);
+        let _e = expect_parses_to("000", SetCurrentLimit(0.), variant);
+        let _e = expect_parses_to("999", SetCurrentLimit(9.99), variant);
+    }
+
+    test cant_parse_malformed(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let _e = expect_cant_parse::<SetCurrentLimit>("", variant);
+        let _e = expect_cant_parse::<SetCurrentLimit>("12", variant);
+        let _e = expect_cant_parse::<SetCurrentLimit>("1234", variant);
+        let _e = expect_cant_parse::<SetCurrentLimit>("-12", variant);
+    }
 }
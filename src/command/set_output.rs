@@ -12,8 +12,12 @@ use std::io;
 pub struct SetOutput(pub OutputState);
 
 impl Command for SetOutput {
+    type Response = ();
+
     const FUNCTION: &'static str = "SOUT";
 
+    const IDEMPOTENT: bool = true;
+
     fn serialize_args<S: io::Write>(
         &self,
         sink: &mut S,
@@ -26,6 +30,21 @@ impl Command for SetOutput {
 
         fmt.serialize_arg(sink, self.0.arg_val() as f32)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        _variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: 0,
+            digits: 1,
+        };
+
+        let val = fmt.parse_arg(raw)? as usize;
+        OutputState::from_arg_val(val)
+            .map(SetOutput)
+            .ok_or(command::Error::MalformedCommand)
+    }
 }
 
 #[cfg(test)]
@@ -38,7 +57,10 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::expect_serializes_to, test_util::any_psu,
+        command::test_util::{
+            expect_cant_parse, expect_parses_to, expect_serializes_to,
+        },
+        test_util::any_psu,
         OutputState,
     };
 
@@ -54,4 +76,18 @@ test_suite! {
             any_psu.val,
         );
     }
+
+    test can_parse(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_parses_to("0", SetOutput(OutputState::On), variant);
+        let _e = expect_parses_to("1", SetOutput(OutputState::Off), variant);
+    }
+
+    test cant_parse_malformed(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<SetOutput>("", variant);
+        let _e = expect_cant_parse::<SetOutput>("2", variant);
+        let _e = expect_cant_parse::<SetOutput>("01", variant);
+    }
+
 }
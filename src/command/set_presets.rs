@@ -1,7 +1,7 @@
 //! Command for setting a predefined set of operating points.
 
 use crate::{
-    command::{self, Command},
+    command::{self, same_encoding, Command, GetPresets, ReadBack},
     response::Presets,
     ArgFormat, OperatingPoint, PresetIndex, SupplyVariant,
 };
@@ -18,6 +18,8 @@ pub struct SetPresets(
 );
 
 impl Command for SetPresets {
+    type Response = ();
+
     const FUNCTION: &'static str = "PROM";
 
     fn serialize_args<S: io::Write>(
@@ -44,6 +46,46 @@ impl Command for SetPresets {
 
         Ok(())
     }
+
+    fn parse_args(
+        raw: &[u8],
+        variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let v_fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: 3,
+        };
+
+        let i_fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: 3,
+        };
+
+        // three 6-byte operating points
+        if raw.len() != 6 * 3 {
+            return Err(command::Error::MalformedCommand);
+        }
+
+        let mut fields = raw.chunks(3);
+        let mut point = || -> command::Result<OperatingPoint> {
+            let voltage = v_fmt.parse_arg(fields.next().unwrap())?;
+            let current = i_fmt.parse_arg(fields.next().unwrap())?;
+
+            Ok(OperatingPoint { voltage, current })
+        };
+
+        Ok(SetPresets(point()?, point()?, point()?))
+    }
+}
+
+impl ReadBack for SetPresets {
+    type Query = GetPresets;
+
+    const QUERY: GetPresets = GetPresets;
+
+    fn is_applied(&self, state: &Presets, variant: &SupplyVariant) -> bool {
+        same_encoding(self, &SetPresets::from(*state), variant)
+    }
 }
 
 impl From<Presets> for SetPresets {
@@ -84,7 +126,10 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::{expect_cant_serialize, expect_serializes_to},
+        command::test_util::{
+            expect_cant_parse, expect_cant_serialize, expect_parses_to,
+            expect_serializes_to,
+        },
         test_util::{
             any_psu, high_voltage_psu, invalid_current_high_voltage,
             invalid_current_low_voltage, invalid_voltage, low_voltage_psu,
@@ -400,4 +445,55 @@ test_suite! {
         let _e = expect_cant_serialize(invalid_p2, any_psu.val);
         let _e = expect_cant_serialize(invalid_p3, any_psu.val);
     }
+
+    test parse_for_low_v_psu(low_voltage_psu) {
+        let _e = expect_parses_to(
+            "011022033044055066",
+            SetPresets(
+                OperatingPoint {
+                    voltage: 1.1,
+                    current: 2.2,
+                },
+                OperatingPoint {
+                    voltage: 3.3,
+                    current: 4.4,
+                },
+                OperatingPoint {
+                    voltage: 5.5,
+                    current: 6.6,
+                },
+            ),
+            low_voltage_psu.val,
+        );
+    }
+
+    test parse_for_high_v_psu(high_voltage_psu) {
+        let _e = expect_parses_to(
+            "050100125300150055",
+            SetPresets(
+                OperatingPoint {
+                    voltage: 5.0,
+                    current: 1.0,
+                },
+                OperatingPoint {
+                    voltage: 12.5,
+                    current: 3.0,
+                },
+                OperatingPoint {
+                    voltage: 15.0,
+                    current: 0.55,
+                },
+            ),
+            high_voltage_psu.val,
+        );
+    }
+
+    test cant_parse_malformed(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<SetPresets>("", variant);
+        let _e = expect_cant_parse::<SetPresets>("01102203304405506", variant);
+        let _e =
+            expect_cant_parse::<SetPresets>("0110220330440550667", variant);
+        let _e = expect_cant_parse::<SetPresets>("01102203304405506x", variant);
+    }
 }
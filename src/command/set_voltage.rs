@@ -1,8 +1,8 @@
 //! Command for setting supply operating voltage.
 
 use crate::{
-    command::{self, Command},
-    response::Voltage,
+    command::{self, same_encoding, Command, GetSettings, ReadBack},
+    response::{Settings, Voltage},
     ArgFormat, SupplyVariant,
 };
 
@@ -13,6 +13,8 @@ use std::io;
 pub struct SetVoltage(pub f32);
 
 impl Command for SetVoltage {
+    type Response = ();
+
     const FUNCTION: &'static str = "VOLT";
 
     fn serialize_args<S: io::Write>(
@@ -27,6 +29,28 @@ impl Command for SetVoltage {
 
         fmt.serialize_arg(sink, self.0)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: 3,
+        };
+
+        fmt.parse_arg(raw).map(SetVoltage)
+    }
+}
+
+impl ReadBack for SetVoltage {
+    type Query = GetSettings;
+
+    const QUERY: GetSettings = GetSettings;
+
+    fn is_applied(&self, state: &Settings, variant: &SupplyVariant) -> bool {
+        same_encoding(self, &SetVoltage(state.voltage), variant)
+    }
 }
 
 impl From<Voltage> for SetVoltage {
@@ -51,10 +75,15 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::{assert_cant_serialize, expect_serializes_to},
+        command::test_util::{
+            assert_cant_serialize, expect_cant_parse, expect_parses_to,
+            expect_serializes_to,
+        },
         test_util::{any_psu, invalid_voltage},
     };
 
+    use galvanic_assert::{expect_that, get_expectation_for, matchers::*};
+
     test can_serialize(any_psu) {
         let variant = any_psu.val;
         let _e = expect_serializes_to(SetVoltage(12.3), "VOLT123\r", variant);
@@ -68,4 +97,28 @@ test_suite! {
     test cant_serialize_if_unrepresentable(any_psu, invalid_voltage) {
         assert_cant_serialize(SetVoltage(invalid_voltage.val), any_psu.val);
     }
+
+    test can_parse(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_parses_to("123", SetVoltage(12.3), variant);
+        let _e = expect_parses_to("000", SetVoltage(0.), variant);
+        let _e = expect_parses_to("999", SetVoltage(99.9), variant);
+    }
+
+    test cant_parse_malformed(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<SetVoltage>("", variant);
+        let _e = expect_cant_parse::<SetVoltage>("12", variant);
+        let _e = expect_cant_parse::<SetVoltage>("1234", variant);
+        let _e = expect_cant_parse::<SetVoltage>("1x3", variant);
+    }
+
+    test reads_back_at_wire_precision(any_psu) {
+        let variant = any_psu.val;
+        let settings = Settings { voltage: 8.2, current: 1. };
+
+        expect_that!(&SetVoltage(8.21).is_applied(&settings, variant), eq(true));
+        expect_that!(&SetVoltage(8.3).is_applied(&settings, variant), eq(false));
+        expect_that!(&SetVoltage(-1.).is_applied(&settings, variant), eq(false));
+    }
 }
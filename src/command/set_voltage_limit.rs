@@ -1,6 +1,6 @@
 //! Command for setting a "soft" voltage limit.
 use crate::{
-    command::{self, Command},
+    command::{self, same_encoding, Command, GetVoltageLimit, ReadBack},
     response::Voltage,
     ArgFormat, SupplyVariant,
 };
@@ -15,6 +15,8 @@ use std::io;
 pub struct SetVoltageLimit(pub f32);
 
 impl Command for SetVoltageLimit {
+    type Response = ();
+
     const FUNCTION: &'static str = "SOVP";
 
     fn serialize_args<S: io::Write>(
@@ -29,6 +31,28 @@ impl Command for SetVoltageLimit {
 
         fmt.serialize_arg(sink, self.0)
     }
+
+    fn parse_args(
+        raw: &[u8],
+        variant: &SupplyVariant,
+    ) -> command::Result<Self> {
+        let fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: 3,
+        };
+
+        fmt.parse_arg(raw).map(SetVoltageLimit)
+    }
+}
+
+impl ReadBack for SetVoltageLimit {
+    type Query = GetVoltageLimit;
+
+    const QUERY: GetVoltageLimit = GetVoltageLimit;
+
+    fn is_applied(&self, state: &Voltage, variant: &SupplyVariant) -> bool {
+        same_encoding(self, &SetVoltageLimit(state.0), variant)
+    }
 }
 
 impl From<Voltage> for SetVoltageLimit {
@@ -53,7 +77,10 @@ test_suite! {
     use super::*;
 
     use crate::{
-        command::test_util::{assert_cant_serialize, expect_serializes_to},
+        command::test_util::{
+            assert_cant_serialize, expect_cant_parse, expect_parses_to,
+            expect_serializes_to,
+        },
         test_util::{any_psu, invalid_voltage},
     };
 
@@ -71,4 +98,19 @@ test_suite! {
         let variant = any_psu.val;
         assert_cant_serialize(SetVoltageLimit(invalid_voltage.val), variant);
     }
+
+    test can_parse(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_parses_to("123", SetVoltageLimit(12.3), variant);
+        let _e = expect_parses_to("000", SetVoltageLimit(0.), variant);
+        let _e = expect_parses_to("999", SetVoltageLimit(99.9), variant);
+    }
+
+    test cant_parse_malformed(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_parse::<SetVoltageLimit>("", variant);
+        let _e = expect_cant_parse::<SetVoltageLimit>("12", variant);
+        let _e = expect_cant_parse::<SetVoltageLimit>("1234", variant);
+        let _e = expect_cant_parse::<SetVoltageLimit>("1x3", variant);
+    }
 }
@@ -62,3 +62,35 @@ pub fn expect_serializes_to<C: Command>(
 
     get_expectation_for!(&written, eq(result))
 }
+
+pub fn expect_parses_to<C>(
+    raw: &str,
+    command: C,
+    variant: &SupplyVariant,
+) -> Expectation
+where
+    C: Command + PartialEq + std::fmt::Debug + 'static,
+{
+    let parsed = C::parse_args(raw.as_bytes(), variant).unwrap();
+
+    get_expectation_for!(&parsed, eq(command))
+}
+
+pub fn expect_cant_parse<C>(raw: &str, variant: &SupplyVariant) -> Expectation
+where
+    C: Command + std::fmt::Debug,
+{
+    let parsed = C::parse_args(raw.as_bytes(), variant);
+
+    get_expectation_for!(&parsed, is_malformed_command_error)
+}
+
+fn is_malformed_command_error<T>(res: &Result<T>) -> MatchResult {
+    let builder = MatchResultBuilder::for_("is malformed command error");
+
+    match res {
+        Err(Error::MalformedCommand) => builder.matched(),
+        Err(_) => builder.failed_because("wrong type of error"),
+        Ok(_) => builder.failed_because("not an error"),
+    }
+}
@@ -23,6 +23,15 @@ impl OutputState {
             OutputState::Off => 1,
         }
     }
+
+    /// Interpret a value used in a command.
+    pub(crate) fn from_arg_val(val: usize) -> Option<Self> {
+        match val {
+            0 => Some(OutputState::On),
+            1 => Some(OutputState::Off),
+            _ => None,
+        }
+    }
 }
 
 /// A supply's output mode.
@@ -65,6 +74,16 @@ impl PresetIndex {
             PresetIndex::Three => 2,
         }
     }
+
+    /// Interpret a concrete index integer.
+    pub(crate) fn from_arg_val(val: usize) -> Option<Self> {
+        match val {
+            0 => Some(PresetIndex::One),
+            1 => Some(PresetIndex::Two),
+            2 => Some(PresetIndex::Three),
+            _ => None,
+        }
+    }
 }
 
 /// A power-supply operating point
@@ -157,19 +176,50 @@ const BK1688B_INST: SupplyVariant = SupplyVariant {
 pub(crate) fn variant_for_max_voltage(
     voltage: f32,
 ) -> Option<&'static SupplyVariant> {
-    for supply in &[BK1685B, BK1687B, BK1688B] {
-        let nom_volt = supply.nominal_max_voltage as f32;
-
-        // This band is pretty arbitrary. It's intentionally lax (I don't think
-        // I've seen a supply ever report more than a couple volts higher than
-        // its nominal value).
-        let top_volt = nom_volt + 10.;
-        if voltage >= nom_volt && voltage < top_volt {
-            return Some(supply);
+    [BK1685B, BK1687B, BK1688B]
+        .iter()
+        .copied()
+        .find(|supply| near_nominal(voltage, supply.nominal_max_voltage))
+}
+
+pub(crate) fn variant_for_maximums(
+    voltage: f32,
+    current: f32,
+) -> Option<&'static SupplyVariant> {
+    variant_for_max_voltage(voltage)
+        .filter(|supply| near_nominal(current, supply.nominal_max_current))
+}
+
+/// Whether a reported maximum is plausible for a nominal one.
+fn near_nominal(reported: f32, nominal: usize) -> bool {
+    let nominal = nominal as f32;
+
+    // This band is pretty arbitrary. It's intentionally lax (I don't think
+    // I've seen a supply ever report more than a couple volts higher than
+    // its nominal value).
+    let top = nominal + 10.;
+    reported >= nominal && reported < top
+}
+
+/// Escape bytes so that they're printable.
+///
+/// `\r`, `\n`, and `\\` stand for themselves, and any other unprintable byte
+/// is written as `\xNN`. This is the format used by transcripts, and in error
+/// messages which show raw bytes.
+pub(crate) fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+
+    for &byte in bytes {
+        match byte {
+            b'\r' => escaped.push_str("\\r"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
         }
     }
 
-    None
+    escaped
 }
 
 pub(crate) struct ArgFormat {
@@ -191,16 +241,43 @@ impl ArgFormat {
         Ok(())
     }
 
-    pub(crate) fn parse(&self, raw: &[u8]) -> response::Result<f32> {
-        use response::Error::MalformedResponse;
+    pub(crate) fn serialize<S: io::Write>(
+        &self,
+        sink: &mut S,
+        val: f32,
+    ) -> response::Result<()> {
+        use response::Error::{ValueUnrepresentable, WriteFailure};
 
-        if raw.len() != self.digits {
-            return Err(MalformedResponse);
+        let value = self.output_val(val).ok_or(ValueUnrepresentable(val))?;
+        write!(sink, "{arg:0width$}", arg = value, width = self.digits)
+            .map_err(WriteFailure)
+    }
+
+    pub(crate) fn parse_arg(&self, raw: &[u8]) -> command::Result<f32> {
+        self.parse(raw, "argument")
+            .map_err(|_| command::Error::MalformedCommand)
+    }
+
+    /// Parse a single numeric field.
+    ///
+    /// Failures are reported as [`InvalidField`](response::Error::InvalidField)
+    /// errors, naming `field`.
+    pub(crate) fn parse(
+        &self,
+        raw: &[u8],
+        field: &'static str,
+    ) -> response::Result<f32> {
+        let invalid = || response::Error::InvalidField {
+            field,
+            raw: response::FieldBytes::new(raw),
+        };
+
+        if raw.len() != self.digits || !raw.iter().all(u8::is_ascii_digit) {
+            return Err(invalid());
         }
 
-        let as_str = str::from_utf8(raw).map_err(|_| MalformedResponse)?;
-        let as_int =
-            usize::from_str_radix(as_str, 10).map_err(|_| MalformedResponse)?;
+        let as_str = str::from_utf8(raw).map_err(|_| invalid())?;
+        let as_int = as_str.parse::<usize>().map_err(|_| invalid())?;
         let val = as_int as f32 / self.factor();
 
         Ok(val)
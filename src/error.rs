@@ -0,0 +1,158 @@
+//! The crate-wide error type
+
+use crate::{
+    command,
+    core::escape,
+    response::{self, Capabilities, Settings},
+    OperatingPoint, PresetIndex,
+};
+
+/// Errors that can arise from talking to a supply.
+///
+/// Communication failures record the command which was in flight, and (for
+/// response failures) exactly what the supply sent back. Together with the
+/// underlying [`response::Error`], which names the offending field where
+/// there is one, this is usually enough to diagnose a problem from a single
+/// log line:
+///
+/// ```
+/// use bk168xb::{transport::Replay, Supply, BK1687B};
+/// use std::error::Error;
+///
+/// let log = "0.000000 TX GETD\\r\n0.003182 RX 030201459\\rOK\\r\n";
+/// let port = Replay::read(log.as_bytes()).unwrap();
+/// let mut supply = Supply::new(port, BK1687B);
+///
+/// let err = supply.status().unwrap_err();
+/// assert!(err.is_malformed());
+/// assert_eq!(
+///     err.to_string(),
+///     "bad response to GETD command: \"030201459\\rOK\\r\"",
+/// );
+/// assert_eq!(
+///     err.source().unwrap().to_string(),
+///     "invalid mode field \"9\" in response",
+/// );
+/// ```
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The command could not be sent.
+    #[error("failed to send {function} command")]
+    Command {
+        /// The function of the command, such as `"VOLT"`.
+        function: String,
+
+        /// Why the command couldn't be sent.
+        #[source]
+        source: command::Error,
+    },
+
+    /// The supply's response could not be received.
+    #[error("bad response to {function} command: \"{}\"", escape(.received))]
+    Response {
+        /// The function of the command, such as `"GETD"`.
+        function: String,
+
+        /// Everything received in place of the expected response.
+        ///
+        /// This may be empty, or an incomplete response.
+        received: Vec<u8>,
+
+        /// Why the response couldn't be received.
+        #[source]
+        source: response::Error,
+    },
+
+    /// The supply's capabilities don't match any known model.
+    #[error("unrecognized supply with capabilities {0:?}")]
+    UnknownVariant(Capabilities),
+
+    /// A preset was selected, but the supply's settings don't match it.
+    ///
+    /// See [`Supply::recall_preset()`](crate::Supply::recall_preset).
+    #[error("selected preset {preset:?}, but settings are {actual:?}")]
+    PresetMismatch {
+        /// The preset which was selected.
+        preset: PresetIndex,
+
+        /// The preset's stored operating point.
+        expected: OperatingPoint,
+
+        /// The supply's settings after selecting it.
+        actual: Settings,
+    },
+}
+
+impl Error {
+    /// The function of the command which was in flight, if any.
+    pub fn function(&self) -> Option<&str> {
+        match self {
+            Error::Command { function, .. } => Some(function),
+            Error::Response { function, .. } => Some(function),
+            Error::UnknownVariant(_) | Error::PresetMismatch { .. } => None,
+        }
+    }
+
+    /// The bytes received in place of a valid response, if any.
+    pub fn received(&self) -> Option<&[u8]> {
+        match self {
+            Error::Response { received, .. } => Some(received),
+            _ => None,
+        }
+    }
+
+    /// Whether the supply returned data which didn't match the expected
+    /// format.
+    ///
+    /// See [`response::Error::is_malformed()`].
+    pub fn is_malformed(&self) -> bool {
+        match self {
+            Error::Response { source, .. } => source.is_malformed(),
+            _ => false,
+        }
+    }
+}
+
+/// A specialized `Result` type for supply operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A failure, before the context of its transaction is attached.
+#[derive(Debug)]
+pub(crate) enum Failure {
+    Command(command::Error),
+    Response(response::Error),
+}
+
+impl Failure {
+    /// Attach the context of a transaction.
+    ///
+    /// # Arguments
+    ///
+    /// - `function`: The function of the command in flight
+    /// - `received`: Everything received in place of a valid response
+    pub(crate) fn into_error(self, function: &str, received: &[u8]) -> Error {
+        let function = function.to_owned();
+
+        match self {
+            Failure::Command(source) => Error::Command { function, source },
+            Failure::Response(source) => Error::Response {
+                function,
+                received: received.to_vec(),
+                source,
+            },
+        }
+    }
+}
+
+impl From<command::Error> for Failure {
+    fn from(err: command::Error) -> Self {
+        Failure::Command(err)
+    }
+}
+
+impl From<response::Error> for Failure {
+    fn from(err: response::Error) -> Self {
+        Failure::Response(err)
+    }
+}
@@ -6,15 +6,54 @@
 //! - BK1685B (60V/5A)
 //! - BK1687B (36V/10A)
 //! - BK1688B (18V/20A)
+//!
+//! Most users will want to start with [`Supply`], which wraps a serial link and
+//! handles pairing each command with its response. The link's reads must time
+//! out, which `transport::SerialTransport` (from the `serialport` feature)
+//! takes care of:
+//!
+//! ```no_run
+//! # #[cfg(feature = "serialport")]
+//! use bk168xb::{
+//!     transport::SerialTransport, OutputState, Supply, BK1687B,
+//! };
+//!
+//! # #[cfg(feature = "serialport")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let port = SerialTransport::open("/dev/ttyUSB0")?;
+//! let mut supply = Supply::new(port, BK1687B);
+//!
+//! supply.set_voltage(12.0)?;
+//! supply.set_current(1.5)?;
+//! supply.set_output(OutputState::On)?;
+//!
+//! let status = supply.status()?;
+//! println!("{}V, {}A", status.voltage, status.current);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "serialport"))]
+//! # fn main() {}
+//! ```
 
 #![warn(missing_docs)]
 #![forbid(unsafe_code)]
 
 mod core;
+mod error;
 #[cfg(test)]
 pub(crate) mod test_util;
 
 pub mod command;
 pub mod response;
+pub mod sim;
+pub mod supply;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transcript;
+pub mod transport;
 
-pub use crate::core::*;
+pub use crate::{
+    core::*,
+    error::{Error, Result},
+    supply::Supply,
+};
@@ -1,10 +1,13 @@
 //! Type-safe BK168xB response parsing
 
+mod any;
 mod capabilities;
 mod core;
 mod current;
 mod error;
+mod parser;
 mod presets;
+mod reader;
 mod settings;
 mod status;
 mod voltage;
@@ -13,6 +16,6 @@ mod voltage;
 pub(crate) mod test_util;
 
 pub use self::{
-    capabilities::*, core::*, current::*, error::*, presets::*, settings::*,
-    status::*, voltage::*,
+    any::*, capabilities::*, core::*, current::*, error::*, parser::*,
+    presets::*, reader::*, settings::*, status::*, voltage::*,
 };
@@ -0,0 +1,224 @@
+//! A response of any type.
+
+use crate::{
+    command::{AnyCommand, Command},
+    response::{
+        parse_frame, Capabilities, Current, Presets, Response, Result,
+        Settings, Status, Voltage,
+    },
+    SupplyVariant,
+};
+
+use std::io;
+
+/// Any one of the supply's responses.
+///
+/// Responses aren't self-describing, so parsing one requires knowing the
+/// command it answers:
+///
+/// ```
+/// use bk168xb::{
+///     command::{AnyCommand, GetVoltageLimit},
+///     response::{AnyResponse, Voltage},
+///     BK1687B,
+/// };
+///
+/// # fn main() -> bk168xb::response::Result<()> {
+/// let command = AnyCommand::GetVoltageLimit(GetVoltageLimit);
+/// let response = AnyResponse::parse(&command, b"123\rOK\r", BK1687B)?;
+///
+/// assert_eq!(response, AnyResponse::Voltage(Voltage(12.3)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+pub enum AnyResponse {
+    /// A bare acknowledgement, sent in response to most commands which
+    /// change the supply's state.
+    Ack(()),
+
+    /// See [`Voltage`].
+    Voltage(Voltage),
+
+    /// See [`Current`].
+    Current(Current),
+
+    /// See [`Settings`].
+    Settings(Settings),
+
+    /// See [`Status`].
+    Status(Status),
+
+    /// See [`Presets`].
+    Presets(Presets),
+
+    /// See [`Capabilities`].
+    Capabilities(Capabilities),
+}
+
+impl AnyResponse {
+    /// Parse a single, complete response (including its `"OK\r"`).
+    ///
+    /// # Arguments
+    ///
+    /// - `command`: The command this is a response to
+    /// - `frame`: The raw response
+    /// - `variant`: Provides information about per-supply parsing quirks
+    pub fn parse(
+        command: &AnyCommand,
+        frame: &[u8],
+        variant: &SupplyVariant,
+    ) -> Result<Self> {
+        match command {
+            AnyCommand::GetSettings(c) => parse_as(c, frame, variant),
+            AnyCommand::GetStatus(c) => parse_as(c, frame, variant),
+            AnyCommand::GetVoltageLimit(c) => parse_as(c, frame, variant),
+            AnyCommand::GetCurrentLimit(c) => parse_as(c, frame, variant),
+            AnyCommand::GetCapabilities(c) => parse_as(c, frame, variant),
+            AnyCommand::GetPresets(c) => parse_as(c, frame, variant),
+            AnyCommand::SetVoltage(c) => parse_as(c, frame, variant),
+            AnyCommand::SetCurrent(c) => parse_as(c, frame, variant),
+            AnyCommand::SetVoltageLimit(c) => parse_as(c, frame, variant),
+            AnyCommand::SetCurrentLimit(c) => parse_as(c, frame, variant),
+            AnyCommand::SetOutput(c) => parse_as(c, frame, variant),
+            AnyCommand::SetPresets(c) => parse_as(c, frame, variant),
+            AnyCommand::SelectPreset(c) => parse_as(c, frame, variant),
+        }
+    }
+
+    /// Write the complete response, as a supply would send it.
+    pub fn serialize<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        match self {
+            AnyResponse::Ack(r) => r.serialize(sink, variant),
+            AnyResponse::Voltage(r) => r.serialize(sink, variant),
+            AnyResponse::Current(r) => r.serialize(sink, variant),
+            AnyResponse::Settings(r) => r.serialize(sink, variant),
+            AnyResponse::Status(r) => r.serialize(sink, variant),
+            AnyResponse::Presets(r) => r.serialize(sink, variant),
+            AnyResponse::Capabilities(r) => r.serialize(sink, variant),
+        }
+    }
+}
+
+/// Parse a response in the format `command` expects.
+fn parse_as<C>(
+    _command: &C,
+    frame: &[u8],
+    variant: &SupplyVariant,
+) -> Result<AnyResponse>
+where
+    C: Command,
+    C::Response: Into<AnyResponse>,
+{
+    parse_frame::<C::Response>(frame, variant).map(Into::into)
+}
+
+impl From<()> for AnyResponse {
+    fn from(r: ()) -> Self {
+        AnyResponse::Ack(r)
+    }
+}
+
+impl From<Voltage> for AnyResponse {
+    fn from(r: Voltage) -> Self {
+        AnyResponse::Voltage(r)
+    }
+}
+
+impl From<Current> for AnyResponse {
+    fn from(r: Current) -> Self {
+        AnyResponse::Current(r)
+    }
+}
+
+impl From<Settings> for AnyResponse {
+    fn from(r: Settings) -> Self {
+        AnyResponse::Settings(r)
+    }
+}
+
+impl From<Status> for AnyResponse {
+    fn from(r: Status) -> Self {
+        AnyResponse::Status(r)
+    }
+}
+
+impl From<Presets> for AnyResponse {
+    fn from(r: Presets) -> Self {
+        AnyResponse::Presets(r)
+    }
+}
+
+impl From<Capabilities> for AnyResponse {
+    fn from(r: Capabilities) -> Self {
+        AnyResponse::Capabilities(r)
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command::{GetPresets, GetStatus, SetOutput},
+        response::Error::WrongLength,
+        test_util::{any_psu, low_voltage_psu},
+        OperatingPoint, OutputMode, OutputState,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test parses_according_to_command(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+
+        let status = AnyResponse::parse(
+            &GetStatus.into(),
+            b"030201451\rOK\r",
+            variant,
+        );
+        expect_that!(&status.unwrap(), eq(AnyResponse::Status(Status {
+            voltage: 3.02,
+            current: 1.45,
+            mode: OutputMode::ConstantCurrent,
+        })));
+
+        let ack = AnyResponse::parse(
+            &SetOutput(OutputState::On).into(),
+            b"OK\r",
+            variant,
+        );
+        expect_that!(&ack.unwrap(), eq(AnyResponse::Ack(())));
+    }
+
+    test rejects_response_to_other_command(any_psu) {
+        let variant = any_psu.val;
+
+        let result = AnyResponse::parse(&GetStatus.into(), b"OK\r", variant);
+
+        assert_that!(&result, has_structure!(Err [
+            is_variant!(WrongLength)
+        ]));
+    }
+
+    test round_trips(any_psu) {
+        let variant = any_psu.val;
+        let point = OperatingPoint { voltage: 1.5, current: 0.5 };
+        let response = AnyResponse::from(Presets(point, point, point));
+
+        let mut raw = Vec::new();
+        response.serialize(&mut raw, variant).unwrap();
+        let parsed = AnyResponse::parse(&GetPresets.into(), &raw, variant);
+
+        expect_that!(&parsed.unwrap(), eq(response));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
     response::{Response, Result},
-    variant_for_max_voltage, ArgFormat, SupplyVariant,
+    variant_for_max_voltage, variant_for_maximums, ArgFormat, SupplyVariant,
 };
 
+use std::io;
+
 /// The maximum output this hardware is capable of.
 ///
 /// These are fixed unlike the "soft" limits
@@ -28,10 +30,22 @@ impl Capabilities {
     /// Determine the variant suggested by these capabilities.
     ///
     /// If `None`, this means the capabilities do not match one of the known
-    /// supplies in this crate. This should be unlikely when actually working
-    /// with a supply.
+    /// supplies in this crate. Both the maximum voltage and the maximum
+    /// current must match. This should be unlikely when actually working with
+    /// a supply.
     pub fn variant(self) -> Option<&'static SupplyVariant> {
-        variant_for_max_voltage(self.max_voltage)
+        variant_for_maximums(self.max_voltage, self.max_current)
+    }
+
+    /// How many decimal places the current is reported with.
+    fn current_decimals(max_voltage: f32) -> usize {
+        // If we can't identify the supply (unlikely), fall back to the most
+        // common case of using one decimal place. This keeps parsing lenient;
+        // autodetection reports an unrecognized supply as an error of its own
+        // (see `Supply::detect()`).
+        variant_for_max_voltage(max_voltage)
+            .map(|v| v.current_decimals)
+            .unwrap_or(1)
     }
 }
 
@@ -47,28 +61,38 @@ impl Response for Capabilities {
             digits: 3,
         };
         let (volt_raw, curr_raw) = raw.split_at(volt_fmt.digits);
-        let voltage = volt_fmt.parse(volt_raw)?;
-
-        // If we can't identify the supply (unlikely), fall back to the most
-        // common case of using two decimal places.
-        //
-        // TODO: Should we just return an error here instead?
-        let current_decimals = variant_for_max_voltage(voltage)
-            .map(|v| v.current_decimals)
-            .unwrap_or(1);
+        let voltage = volt_fmt.parse(volt_raw, "max voltage")?;
 
         let curr_fmt = ArgFormat {
-            decimals: current_decimals,
+            decimals: Self::current_decimals(voltage),
             digits: 3,
         };
 
-        let current = curr_fmt.parse(curr_raw)?;
+        let current = curr_fmt.parse(curr_raw, "max current")?;
 
         Ok(Capabilities {
             max_voltage: voltage,
             max_current: current,
         })
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        _variant: &SupplyVariant,
+    ) -> Result<()> {
+        let volt_fmt = ArgFormat {
+            decimals: 1,
+            digits: 3,
+        };
+        let curr_fmt = ArgFormat {
+            decimals: Self::current_decimals(self.max_voltage),
+            digits: 3,
+        };
+
+        volt_fmt.serialize(sink, self.max_voltage)?;
+        curr_fmt.serialize(sink, self.max_current)
+    }
 }
 
 #[cfg(test)]
@@ -79,8 +103,11 @@ galvanic_test::test_suite! {
 
     use crate::{
         response::{
-            test_util::{expect_deserialize_error, expect_deserializes_to},
-            Error::MalformedResponse,
+            test_util::{
+                expect_deserialize_error, expect_deserializes_to,
+                expect_round_trips, expect_serializes_to, invalid_field,
+            },
+            Error::WrongLength,
         },
         test_util::any_psu,
         BK1685B, BK1687B, BK1688B,
@@ -313,23 +340,71 @@ galvanic_test::test_suite! {
         expect_that!(&ninetynine.variant(), eq(None));
     }
 
+    test requires_current_to_match() {
+        let low_current = Capabilities {
+            max_voltage: 36.0,
+            max_current: 5.0,
+        };
+        expect_that!(&low_current.variant(), eq(None));
+
+        let high_current = Capabilities {
+            max_voltage: 60.0,
+            max_current: 20.0,
+        };
+        expect_that!(&high_current.variant(), eq(None));
+    }
+
     test fails_to_parse_invalid_settings(any_psu) {
         let _e = expect_deserialize_error::<Capabilities>(
             "x00000\rOK\r",
-            MalformedResponse,
+            invalid_field("max voltage", "x00"),
             any_psu.val,
         );
 
         let _e = expect_deserialize_error::<Capabilities>(
             "000x00\rOK\r",
-            MalformedResponse,
+            invalid_field("max current", "x00"),
             any_psu.val,
         );
 
         let _e = expect_deserialize_error::<Capabilities>(
             "1234567\rOK\r",
-            MalformedResponse,
+            WrongLength { expected: 10, actual: 11 },
             any_psu.val,
         );
     }
+
+    test serializes_for_each_supply(any_psu) {
+        let variant = any_psu.val;
+
+        let _e = expect_serializes_to(
+            Capabilities { max_voltage: 60.0, max_current: 5.0 },
+            "600500\rOK\r",
+            variant,
+        );
+        let _e = expect_serializes_to(
+            Capabilities { max_voltage: 36.0, max_current: 10.0 },
+            "360100\rOK\r",
+            variant,
+        );
+        let _e = expect_serializes_to(
+            Capabilities { max_voltage: 18.0, max_current: 20.0 },
+            "180200\rOK\r",
+            variant,
+        );
+    }
+
+    test round_trips(any_psu) {
+        let variant = any_psu.val;
+
+        for supply in &[BK1685B, BK1687B, BK1688B] {
+            let _e = expect_round_trips(
+                Capabilities {
+                    max_voltage: supply.nominal_max_voltage as f32,
+                    max_current: supply.nominal_max_current as f32,
+                },
+                variant,
+            );
+        }
+    }
 }
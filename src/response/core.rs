@@ -10,16 +10,18 @@ use std::{io, str};
 /// This forms the core of the receive end of a power supply interface. Because
 /// the interface is not self-describing, this trait relies on a-priori
 /// knowledge of the expected response. This is the core reason for the design
-/// of the entire parsing structure, which is intended to allow compile-time
-/// enforcement of command/response pairings.
+/// of the entire parsing structure, which allows compile-time enforcement of
+/// command/response pairings through
+/// [`Command::Response`](crate::command::Command::Response).
 ///
 /// Each response consists of a fixed-sized argument field, followed by a
 /// literal `"OK\r"` string. The specifics for each particular format can be
 /// found in the programming manual for the supplies in question, beginning on
 /// page 11.
 ///
-/// This trait abstracts over the parsing of the argument field. Commmon parsing
-/// logic is implemented in [`ResponseSource`](crate::response::ResponseSource).
+/// This trait abstracts over the parsing (and serialization) of the argument
+/// field. Commmon parsing logic is implemented by each
+/// [`ResponseSource`](crate::response::ResponseSource).
 pub trait Response: Sized + PartialEq {
     /// How many bytes make up the argument for this response.
     ///
@@ -30,6 +32,54 @@ pub trait Response: Sized + PartialEq {
 
     /// Parse the argument for this response.
     fn parse_args(raw: &[u8], variant: &SupplyVariant) -> Result<Self>;
+
+    /// Write the argument for this response to the specified sink.
+    ///
+    /// This is the inverse of [`parse_args()`](Response::parse_args).
+    ///
+    /// # Arguments
+    ///
+    /// - `sink`: Where to write arguments
+    /// - `variant`: Provides information about per-supply serialization quirks
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()>;
+
+    /// Write the complete response, as a supply would send it.
+    ///
+    /// ```
+    /// use bk168xb::{
+    ///     response::{Response, Settings},
+    ///     BK1687B,
+    /// };
+    ///
+    /// # fn main() -> bk168xb::response::Result<()> {
+    /// let settings = Settings {
+    ///     voltage: 12.3,
+    ///     current: 4.5,
+    /// };
+    ///
+    /// let mut raw = Vec::new();
+    /// settings.serialize(&mut raw, BK1687B)?;
+    /// assert_eq!(raw, b"123045\rOK\r");
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn serialize<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        self.serialize_args(sink, variant)?;
+        if Self::arg_bytes() != 0 {
+            sink.write_all(b"\r").map_err(Error::WriteFailure)?;
+        }
+        sink.write_all(OK.as_bytes()).map_err(Error::WriteFailure)?;
+
+        Ok(())
+    }
 }
 
 impl Response for () {
@@ -40,66 +90,87 @@ impl Response for () {
     fn parse_args(_raw: &[u8], _variant: &SupplyVariant) -> Result<Self> {
         Ok(())
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        _sink: &mut S,
+        _variant: &SupplyVariant,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// The receiving side of a power-supply communication link.
-pub(crate) trait ResponseSource {
+///
+/// Serial ports rarely deliver a response in a single read, so an
+/// [`io::Read`] must be wrapped in a
+/// [`ResponseReader`](crate::response::ResponseReader) to become a response
+/// source.
+pub trait ResponseSource {
     /// Receive a response from the power supply.
     ///
-    /// Should almost always be paired with a call to
-    /// [`send_command()`](crate::command::CommandSink::send_command).
+    /// Should almost always be paired with sending a
+    /// [`Command`](crate::command::Command).
     fn get_response<R: Response>(
         &mut self,
         variant: &SupplyVariant,
     ) -> Result<R>;
 }
 
-impl<S> ResponseSource for S
-where
-    S: io::Read,
-{
-    fn get_response<R: Response>(
-        &mut self,
-        variant: &SupplyVariant,
-    ) -> Result<R> {
-        use Error::*;
-
-        let arg_bytes = R::arg_bytes();
-        let before_ok_bytes = if arg_bytes != 0 {
-            // one more for the separator
-            arg_bytes + 1
-        } else {
-            arg_bytes
-        };
-        let total_bytes = before_ok_bytes + OK.len();
-
-        let mut buf: Vec<_> = vec![0; total_bytes];
-        let read = self.read(&mut buf)?;
-        if read == 0 {
-            return Err(NoResponse);
-        } else if read != total_bytes {
-            return Err(MalformedResponse);
-        }
+const OK: &str = "OK\r";
 
-        let (before_ok, ok) = buf.split_at(before_ok_bytes);
-        verify_ok(ok)?;
+/// The number of bytes in a complete response, including its `"OK\r"`.
+pub(crate) fn frame_bytes<R: Response>() -> usize {
+    let arg_bytes = R::arg_bytes();
+    let before_ok_bytes = if arg_bytes != 0 {
+        // one more for the separator
+        arg_bytes + 1
+    } else {
+        arg_bytes
+    };
 
-        let args = if arg_bytes != 0 {
-            let (&sep, args) =
-                before_ok.split_last().ok_or(MalformedResponse)?;
-            verify_sep(sep)?;
+    before_ok_bytes + OK.len()
+}
 
-            args
-        } else {
-            before_ok
-        };
-        let resp = R::parse_args(args, variant)?;
+/// Find the end of the first complete response in `raw`.
+///
+/// Every response is terminated by `"OK\r"`, and no valid argument field can
+/// contain that sequence. The returned index is one past the terminator.
+pub(crate) fn frame_end(raw: &[u8]) -> Option<usize> {
+    raw.windows(OK.len())
+        .position(|w| w == OK.as_bytes())
+        .map(|pos| pos + OK.len())
+}
 
-        Ok(resp)
+/// Parse a single, complete response (including its `"OK\r"`).
+pub(crate) fn parse_frame<R: Response>(
+    frame: &[u8],
+    variant: &SupplyVariant,
+) -> Result<R> {
+    use Error::*;
+
+    if frame.len() != frame_bytes::<R>() {
+        return Err(WrongLength {
+            expected: frame_bytes::<R>(),
+            actual: frame.len(),
+        });
     }
-}
 
-const OK: &str = "OK\r";
+    let (before_ok, ok) = frame.split_at(frame.len() - OK.len());
+    verify_ok(ok)?;
+
+    let args = if R::arg_bytes() != 0 {
+        let (&sep, args) = before_ok.split_last().ok_or(MalformedResponse)?;
+        verify_sep(sep)?;
+
+        args
+    } else {
+        before_ok
+    };
+    let resp = R::parse_args(args, variant)?;
+
+    Ok(resp)
+}
 
 fn verify_ok(raw: &[u8]) -> Result<()> {
     if raw != OK.as_bytes() {
@@ -125,8 +196,9 @@ galvanic_test::test_suite! {
         response::{
             test_util::{
                 assert_deserializes_to, expect_deserialize_error,
-                expect_deserialize_error_from, invalid_ack, invalid_sep,
-                io_error, valid_ack, valid_sep, ErrorAfter,
+                expect_deserialize_error_from, expect_serializes_to,
+                invalid_ack, invalid_sep, io_error, valid_ack, valid_sep,
+                ErrorAfter,
             },
             Capabilities, Current,
             Error::*,
@@ -136,16 +208,30 @@ galvanic_test::test_suite! {
         SupplyVariant,
     };
 
-    use galvanic_assert::Expectation;
+    use super::{frame_bytes, OK};
+
+    use galvanic_assert::{assert_that, is_variant, Expectation};
 
     use core::fmt::Debug;
     use std::io;
-    use std::iter::repeat;
 
     test can_parse(any_psu, valid_ack) {
         assert_deserializes_to(valid_ack.val, (), any_psu.val);
     }
 
+    test can_serialize(any_psu) {
+        let _e = expect_serializes_to((), "OK\r", any_psu.val);
+    }
+
+    test propagates_write_error(any_psu) {
+        let mut sink: &mut [u8] = &mut [0; 4];
+        let settings = Settings { voltage: 1., current: 1. };
+
+        let err = settings.serialize(&mut sink, any_psu.val).unwrap_err();
+
+        assert_that!(&err, is_variant!(WriteFailure));
+    }
+
     test fails_to_parse_with_no_response(any_psu) {
         let variant = any_psu.val;
 
@@ -179,7 +265,12 @@ galvanic_test::test_suite! {
             variant: &SupplyVariant,
             ack: &str
         ) -> Expectation {
-            expect_deserialize_error::<R>(ack, MalformedResponse, variant)
+            let expected = WrongLength {
+                expected: frame_bytes::<R>(),
+                actual: ack.len(),
+            };
+
+            expect_deserialize_error::<R>(ack, expected, variant)
         }
     }
 
@@ -200,8 +291,12 @@ galvanic_test::test_suite! {
         ) -> Expectation {
             let mut resp = dummy_arg_for::<R>();
             resp.push_str(ack);
+            let expected = WrongLength {
+                expected: frame_bytes::<R>(),
+                actual: resp.len(),
+            };
 
-            expect_deserialize_error::<R>(&resp, MalformedResponse, variant)
+            expect_deserialize_error::<R>(&resp, expected, variant)
         }
     }
 
@@ -231,7 +326,17 @@ galvanic_test::test_suite! {
             resp.push(sep);
             resp.push_str(ack);
 
-            expect_deserialize_error::<R>(&resp, MalformedResponse, variant)
+            // Without an argument, the separator is just an extra byte
+            let expected = if R::arg_bytes() == 0 {
+                WrongLength {
+                    expected: frame_bytes::<R>(),
+                    actual: resp.len(),
+                }
+            } else {
+                MalformedResponse
+            };
+
+            expect_deserialize_error::<R>(&resp, expected, variant)
         }
     }
 
@@ -261,8 +366,12 @@ galvanic_test::test_suite! {
             resp.push(sep);
             resp.push(sep);
             resp.push_str(ack);
+            let expected = WrongLength {
+                expected: frame_bytes::<R>(),
+                actual: resp.len(),
+            };
 
-            expect_deserialize_error::<R>(&resp, MalformedResponse, variant)
+            expect_deserialize_error::<R>(&resp, expected, variant)
         }
     }
 
@@ -290,12 +399,23 @@ galvanic_test::test_suite! {
             ack: &str
         ) -> Expectation {
             let mut resp = dummy_arg_for::<R>();
-            if resp.len() != 0 {
+            if !resp.is_empty() {
                 resp.push(sep);
             }
             resp.push_str(ack);
 
-            expect_deserialize_error::<R>(&resp, MalformedResponse, variant)
+            // Some invalid acks still end in a terminator, which makes them one
+            // byte too long. The rest never end.
+            let expected = if resp.ends_with(OK) {
+                WrongLength {
+                    expected: frame_bytes::<R>(),
+                    actual: resp.len(),
+                }
+            } else {
+                MalformedResponse
+            };
+
+            expect_deserialize_error::<R>(&resp, expected, variant)
         }
     }
 
@@ -317,16 +437,22 @@ galvanic_test::test_suite! {
         ) -> Expectation {
             let mut data = "".as_bytes();
             let mut source = ErrorAfter::new(err, &mut data);
+            // Interrupted reads are retried, until there's nothing to show
+            let expected = if err == io::ErrorKind::Interrupted {
+                NoResponse
+            } else {
+                ReadFailure(err.into())
+            };
 
             expect_deserialize_error_from::<R, _>(
                 &mut source,
-                ReadFailure(err.into()),
+                expected,
                 variant
             )
         }
     }
 
     fn dummy_arg_for<R: Response>() -> String {
-        repeat('0').take(R::arg_bytes()).collect()
+        "0".repeat(R::arg_bytes())
     }
 }
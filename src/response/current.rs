@@ -3,6 +3,8 @@ use crate::{
     ArgFormat, SupplyVariant,
 };
 
+use std::io;
+
 /// A power-supply response for a single current value.
 ///
 /// This is the response format used by the
@@ -21,10 +23,23 @@ impl Response for Current {
             digits: Self::arg_bytes(),
         };
 
-        let current = current_fmt.parse(raw)?;
+        let current = current_fmt.parse(raw, "current")?;
 
         Ok(Current(current))
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        let current_fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: Self::arg_bytes(),
+        };
+
+        current_fmt.serialize(sink, self.0)
+    }
 }
 
 impl From<f32> for Current {
@@ -42,8 +57,10 @@ galvanic_test::test_suite! {
     use crate::{
         response::{
             test_util::{
-                assert_deserialize_error, assert_deserializes_to, invalid_num,
-                valid_ack, valid_num, valid_sep,
+                assert_deserialize_error, assert_deserializes_to,
+                expect_cant_serialize, expect_round_trips,
+                expect_serializes_to, invalid_field, invalid_num, valid_ack,
+                valid_num, valid_sep,
             },
             Error,
         },
@@ -87,13 +104,41 @@ galvanic_test::test_suite! {
         invalid_num,
         valid_ack
     ) {
-        let mut resp = invalid_num.val.to_owned();
+        let num = invalid_num.val;
+        let mut resp = num.to_owned();
         resp.push('\r');
         resp.push_str(valid_ack.val);
-        assert_deserialize_error::<Current>(
-            &resp,
-            Error::MalformedResponse,
-            any_psu.val
-        );
+        // A response ends at its first terminator, even one inside the field
+        let framed = resp.find(valid_ack.val).unwrap() + valid_ack.val.len();
+        let expected = if framed == 7 {
+            invalid_field("current", num)
+        } else {
+            Error::WrongLength { expected: 7, actual: framed }
+        };
+        assert_deserialize_error::<Current>(&resp, expected, any_psu.val);
+    }
+
+    test serialize_for_low_voltage(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let _e = expect_serializes_to(Current(12.3), "123\rOK\r", variant);
+        let _e = expect_serializes_to(Current(0.), "000\rOK\r", variant);
+        let _e = expect_cant_serialize(Current(100.), variant);
+    }
+
+    test serialize_for_high_voltage(high_voltage_psu) {
+        let variant = high_voltage_psu.val;
+        let _e = expect_serializes_to(Current(1.23), "123\rOK\r", variant);
+        let _e = expect_serializes_to(Current(0.), "000\rOK\r", variant);
+        let _e = expect_cant_serialize(Current(10.), variant);
+    }
+
+    test round_trips_for_low_voltage(low_voltage_psu, valid_num) {
+        let arg = valid_num.val;
+        let _e = expect_round_trips(Current(arg.one_decimal), low_voltage_psu.val);
+    }
+
+    test round_trips_for_high_voltage(high_voltage_psu, valid_num) {
+        let arg = valid_num.val;
+        let _e = expect_round_trips(Current(arg.two_decimals), high_voltage_psu.val);
     }
 }
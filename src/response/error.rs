@@ -1,6 +1,8 @@
 //! Errors that can arise from parsing BK responses
 
-use std::io;
+use crate::core::escape;
+
+use std::{fmt, io};
 
 /// Errors that can arise from `Response` functions.
 #[derive(Debug, thiserror::Error)]
@@ -10,6 +12,28 @@ pub enum Error {
     #[error("malformed command response")]
     MalformedResponse,
 
+    /// The PSU returned a response of the wrong length for its format.
+    ///
+    /// Lengths include the trailing `"OK\r"`.
+    #[error("response is {actual} bytes long, expected {expected}")]
+    WrongLength {
+        /// The length of a response in the expected format.
+        expected: usize,
+
+        /// The length of the response actually received.
+        actual: usize,
+    },
+
+    /// One of the response's fields couldn't be parsed.
+    #[error("invalid {field} field \"{raw}\" in response")]
+    InvalidField {
+        /// The name of the field, such as `"voltage"` or `"mode"`.
+        field: &'static str,
+
+        /// The field's raw contents.
+        raw: FieldBytes,
+    },
+
     /// The PSU returned no data.
     ///
     /// Note: this is also returned if a timeout error occurred.
@@ -19,6 +43,85 @@ pub enum Error {
     /// The source returned an error when reading data.
     #[error("failed to read response")]
     ReadFailure(#[from] io::Error),
+
+    /// The response contained a value which is invalid for its format.
+    #[error("unrepresentable value in response: {0}")]
+    ValueUnrepresentable(f32),
+
+    /// The sink returned an error while writing the response.
+    #[error("failed to write response")]
+    WriteFailure(#[source] io::Error),
+}
+
+impl Error {
+    /// Whether the PSU returned data which didn't match the expected format.
+    ///
+    /// This covers [`MalformedResponse`](Error::MalformedResponse), as well as
+    /// the more specific [`WrongLength`](Error::WrongLength) and
+    /// [`InvalidField`](Error::InvalidField).
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            Error::MalformedResponse
+                | Error::WrongLength { .. }
+                | Error::InvalidField { .. }
+        )
+    }
+}
+
+/// The raw contents of a response field, stored inline.
+///
+/// Fields are only a few bytes long, so this doesn't allocate. Any bytes
+/// beyond the first [`CAPACITY`](FieldBytes::CAPACITY) are dropped.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct FieldBytes {
+    buf: [u8; FieldBytes::CAPACITY],
+    len: usize,
+}
+
+impl FieldBytes {
+    /// The most bytes which are kept.
+    pub const CAPACITY: usize = 8;
+
+    /// Copy a field's contents.
+    pub fn new(raw: &[u8]) -> Self {
+        let mut buf = [0; FieldBytes::CAPACITY];
+        let kept = raw.len().min(FieldBytes::CAPACITY);
+        buf[..kept].copy_from_slice(&raw[..kept]);
+
+        FieldBytes {
+            buf,
+            len: raw.len(),
+        }
+    }
+
+    /// The bytes which were kept.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len.min(FieldBytes::CAPACITY)]
+    }
+
+    /// Whether the field was too long to keep all of.
+    pub fn is_truncated(&self) -> bool {
+        self.len > FieldBytes::CAPACITY
+    }
+}
+
+impl fmt::Debug for FieldBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FieldBytes(\"{}\")", self)
+    }
+}
+
+impl fmt::Display for FieldBytes {
+    /// Unprintable bytes are escaped, as in a transcript.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", escape(self.as_bytes()))?;
+        if self.is_truncated() {
+            write!(f, "...")?;
+        }
+
+        Ok(())
+    }
 }
 
 /// A specialized `Result` type for `Response` operations.
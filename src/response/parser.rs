@@ -0,0 +1,274 @@
+use crate::{
+    response::{parse_frame, Error, Response, Result},
+    SupplyVariant,
+};
+
+use std::marker::PhantomData;
+
+/// The largest response a [`Parser`] can hold.
+///
+/// This comfortably exceeds the size of any response the supplies send.
+pub const MAX_RESPONSE_BYTES: usize = 64;
+
+/// The outcome of feeding bytes to a [`Parser`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Parsed<R> {
+    /// All input was consumed without completing a response.
+    NeedMore,
+
+    /// A complete response was parsed.
+    Done(R),
+}
+
+/// An incremental, push-based response parser.
+///
+/// Unlike [`ResponseSource`](crate::response::ResponseSource), this performs
+/// no I/O: bytes are pushed in as they arrive, from whatever source. It also
+/// performs no allocation, even when reporting an error, making it suitable
+/// for event loops and embedded targets alike.
+///
+/// ```
+/// use bk168xb::{
+///     response::{Parsed, Parser, Voltage},
+///     BK1687B,
+/// };
+///
+/// # fn main() -> bk168xb::response::Result<()> {
+/// let mut parser = Parser::<Voltage>::new();
+///
+/// let mut input = "12".as_bytes();
+/// assert_eq!(parser.feed(&mut input, BK1687B)?, Parsed::NeedMore);
+///
+/// let mut input = "3\rOK\rleftover".as_bytes();
+/// let parsed = parser.feed(&mut input, BK1687B)?;
+/// assert_eq!(parsed, Parsed::Done(Voltage(12.3)));
+/// assert_eq!(input, "leftover".as_bytes());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Parser<R> {
+    buf: [u8; MAX_RESPONSE_BYTES],
+    len: usize,
+    overflowed: bool,
+    response: PhantomData<fn() -> R>,
+}
+
+impl<R> Parser<R>
+where
+    R: Response,
+{
+    /// Create a parser, ready for the start of a response.
+    pub fn new() -> Self {
+        Parser {
+            buf: [0; MAX_RESPONSE_BYTES],
+            len: 0,
+            overflowed: false,
+            response: PhantomData,
+        }
+    }
+
+    /// Feed bytes to the parser.
+    ///
+    /// Bytes are consumed from the front of `input` only until the end of a
+    /// response -- on return, `input` holds whatever was left over. This is
+    /// true whether that response parsed successfully or not, so after an
+    /// error the remaining input can be fed to the parser again.
+    ///
+    /// # Arguments
+    ///
+    /// - `input`: Bytes received from the supply
+    /// - `variant`: Provides information about per-supply parsing quirks
+    pub fn feed(
+        &mut self,
+        input: &mut &[u8],
+        variant: &SupplyVariant,
+    ) -> Result<Parsed<R>> {
+        while let Some((&byte, rest)) = input.split_first() {
+            *input = rest;
+            self.push(byte);
+
+            if self.buf[..self.len].ends_with(b"OK\r") {
+                let overflowed = self.overflowed;
+                let len = self.len;
+                self.reset();
+
+                if overflowed {
+                    return Err(Error::MalformedResponse);
+                }
+
+                return parse_frame(&self.buf[..len], variant)
+                    .map(Parsed::Done);
+            }
+        }
+
+        Ok(Parsed::NeedMore)
+    }
+
+    /// Discard any partially-received response.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    /// Whether part of a response has been received.
+    pub fn in_progress(&self) -> bool {
+        self.len != 0
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.len == self.buf.len() {
+            // This can't be a valid response, but we still need to find where
+            // it ends. Only the tail is needed to recognize the terminator.
+            let tail = self.len - 2;
+            self.buf.copy_within(tail.., 0);
+            self.len = 2;
+            self.overflowed = true;
+        }
+
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+}
+
+impl<R> Default for Parser<R>
+where
+    R: Response,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        response::{
+            Error::{InvalidField, MalformedResponse, WrongLength},
+            FieldBytes, Presets, Settings, Status,
+        },
+        test_util::{any_psu, low_voltage_psu},
+        OperatingPoint, OutputMode,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test parses_byte_at_a_time(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let raw = "050010\r125030\r150005\rOK\r".as_bytes();
+        let mut parser = Parser::<Presets>::new();
+
+        let (last, most) = raw.split_last().unwrap();
+        for byte in most {
+            let mut input = std::slice::from_ref(byte);
+            let parsed = parser.feed(&mut input, variant).unwrap();
+            expect_that!(&parsed, eq(Parsed::NeedMore));
+            expect_that!(&input.is_empty(), eq(true));
+        }
+
+        let mut input = std::slice::from_ref(last);
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::Done(Presets(
+            OperatingPoint { voltage: 5.0, current: 1.0 },
+            OperatingPoint { voltage: 12.5, current: 3.0 },
+            OperatingPoint { voltage: 15.0, current: 0.5 },
+        ))));
+        expect_that!(&parser.in_progress(), eq(false));
+    }
+
+    test leaves_following_bytes(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let mut input = "123456\rOK\r030201451\rOK\r".as_bytes();
+
+        let mut settings_parser = Parser::<Settings>::new();
+        expect_that!(
+            &settings_parser.feed(&mut input, variant).unwrap(),
+            eq(Parsed::Done(Settings { voltage: 12.3, current: 45.6 }))
+        );
+        expect_that!(&input, eq("030201451\rOK\r".as_bytes()));
+
+        let mut status_parser = Parser::<Status>::new();
+        expect_that!(
+            &status_parser.feed(&mut input, variant).unwrap(),
+            eq(Parsed::Done(Status {
+                voltage: 3.02,
+                current: 1.45,
+                mode: OutputMode::ConstantCurrent,
+            }))
+        );
+        expect_that!(&input.is_empty(), eq(true));
+    }
+
+    test error_consumes_whole_response(any_psu) {
+        let variant = any_psu.val;
+        let mut input = "OK\rOK\r".as_bytes();
+        let mut parser = Parser::<Settings>::new();
+
+        let err = parser.feed(&mut input, variant).unwrap_err();
+        assert_that!(&err, has_structure!(WrongLength {
+            expected: eq(10),
+            actual: eq(3)
+        }));
+        expect_that!(&input, eq("OK\r".as_bytes()));
+
+        let mut parser = Parser::<()>::new();
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::Done(())));
+    }
+
+    test reports_invalid_field(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let mut input = "0302x1451\rOK\r".as_bytes();
+        let mut parser = Parser::<Status>::new();
+
+        let err = parser.feed(&mut input, variant).unwrap_err();
+        assert_that!(&err, has_structure!(InvalidField {
+            field: eq("current"),
+            raw: eq(FieldBytes::new(b"x145"))
+        }));
+        expect_that!(&input.is_empty(), eq(true));
+    }
+
+    test rejects_oversized_response(any_psu) {
+        let variant = any_psu.val;
+        let garbage = "0".repeat(MAX_RESPONSE_BYTES * 2);
+        let mut parser = Parser::<()>::new();
+
+        let mut input = garbage.as_bytes();
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::NeedMore));
+
+        let mut input = "OK\rOK\r".as_bytes();
+        let err = parser.feed(&mut input, variant);
+        assert_that!(
+            &err,
+            has_structure!(Err [is_variant!(MalformedResponse)])
+        );
+
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::Done(())));
+    }
+
+    test can_be_reset(any_psu) {
+        let variant = any_psu.val;
+        let mut parser = Parser::<()>::new();
+
+        let mut input = "garbage".as_bytes();
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::NeedMore));
+        expect_that!(&parser.in_progress(), eq(true));
+
+        parser.reset();
+
+        let mut input = "OK\r".as_bytes();
+        let parsed = parser.feed(&mut input, variant).unwrap();
+        expect_that!(&parsed, eq(Parsed::Done(())));
+    }
+}
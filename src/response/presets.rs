@@ -1,8 +1,14 @@
 use crate::{
-    response::{Error::MalformedResponse, Response, Result},
+    response::{
+        Error::{InvalidField, MalformedResponse, WriteFailure},
+        FieldBytes, Response, Result,
+    },
     ArgFormat, OperatingPoint, PresetIndex, SupplyVariant,
 };
-use std::ops::{Index, IndexMut};
+use std::{
+    io,
+    ops::{Index, IndexMut},
+};
 
 /// The supply's pre-configured operating points.
 ///
@@ -28,15 +34,17 @@ impl Response for Presets {
         };
 
         let mut op_points = raw.split(|&b| b == b'\r');
+        let mut parse_next = |names: [&'static str; 3]| {
+            let raw = op_points.next().ok_or(MalformedResponse)?;
+            Self::parse_operating_point(raw, names, &v_fmt, &i_fmt)
+        };
 
-        let p0_raw = op_points.next().ok_or(MalformedResponse)?;
-        let p0 = Self::parse_operating_point(p0_raw, &v_fmt, &i_fmt)?;
-
-        let p1_raw = op_points.next().ok_or(MalformedResponse)?;
-        let p1 = Self::parse_operating_point(p1_raw, &v_fmt, &i_fmt)?;
-
-        let p2_raw = op_points.next().ok_or(MalformedResponse)?;
-        let p2 = Self::parse_operating_point(p2_raw, &v_fmt, &i_fmt)?;
+        let p0 =
+            parse_next(["preset 1", "preset 1 voltage", "preset 1 current"])?;
+        let p1 =
+            parse_next(["preset 2", "preset 2 voltage", "preset 2 current"])?;
+        let p2 =
+            parse_next(["preset 3", "preset 3 voltage", "preset 3 current"])?;
 
         if op_points.next().is_some() {
             return Err(MalformedResponse);
@@ -44,17 +52,55 @@ impl Response for Presets {
 
         Ok(Presets(p0, p1, p2))
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        let v_fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: 3,
+        };
+        let i_fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: 3,
+        };
+
+        for (i, point) in [self.0, self.1, self.2].iter().enumerate() {
+            if i != 0 {
+                sink.write_all(b"\r").map_err(WriteFailure)?;
+            }
+            v_fmt.serialize(sink, point.voltage)?;
+            i_fmt.serialize(sink, point.current)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Presets {
+    /// Parse one operating point.
+    ///
+    /// `names` gives the field names of the whole point, its voltage, and its
+    /// current, for error reporting.
     fn parse_operating_point(
         raw: &[u8],
+        names: [&'static str; 3],
         v_fmt: &ArgFormat,
         i_fmt: &ArgFormat,
     ) -> Result<OperatingPoint> {
+        let [name, v_name, i_name] = names;
+        if raw.len() != v_fmt.digits + i_fmt.digits {
+            return Err(InvalidField {
+                field: name,
+                raw: FieldBytes::new(raw),
+            });
+        }
+
         let (v_raw, i_raw) = raw.split_at(v_fmt.digits);
-        let voltage = v_fmt.parse(v_raw)?;
-        let current = i_fmt.parse(i_raw)?;
+        let voltage = v_fmt.parse(v_raw, v_name)?;
+        let current = i_fmt.parse(i_raw, i_name)?;
 
         Ok(OperatingPoint { voltage, current })
     }
@@ -90,8 +136,12 @@ galvanic_test::test_suite! {
 
     use crate::{
         response::{
-            test_util::{expect_deserialize_error, expect_deserializes_to},
-            Error::MalformedResponse,
+            test_util::{
+                expect_cant_serialize, expect_deserialize_error,
+                expect_deserializes_to, expect_round_trips,
+                expect_serializes_to, invalid_field,
+            },
+            Error::WrongLength,
         },
         test_util::{any_psu, high_voltage_psu, low_voltage_psu},
     };
@@ -259,14 +309,14 @@ galvanic_test::test_suite! {
     test fails_to_parse_with_missing_fields(any_psu) {
         let _e = expect_deserialize_error::<Presets>(
             "015015\r025025\rOK\r",
-            MalformedResponse,
+            WrongLength { expected: 24, actual: 17 },
             any_psu.val,
         );
 
         // Correct character count => should exercise the parse_args function
         let _e = expect_deserialize_error::<Presets>(
             "015000015\r0250000025\rOK\r",
-            MalformedResponse,
+            invalid_field("preset 1", "015000015"),
             any_psu.val,
         );
     }
@@ -274,15 +324,57 @@ galvanic_test::test_suite! {
     test fails_to_parse_with_extra_fields(any_psu) {
         let _e = expect_deserialize_error::<Presets>(
             "111111\r222222\r333333\r444444\rOK\r",
-            MalformedResponse,
+            WrongLength { expected: 24, actual: 31 },
             any_psu.val,
         );
 
         // Correct character count => should exercise the parse_args function
         let _e = expect_deserialize_error::<Presets>(
             "111111\r22222\r33333\r9\rOK\r",
-            MalformedResponse,
+            invalid_field("preset 2", "22222"),
             any_psu.val,
         );
     }
+
+    test serialize_for_low_voltage_psu(low_voltage_psu) {
+        let _e = expect_serializes_to(
+            Presets(
+                OperatingPoint { voltage: 5.0, current: 1.0 },
+                OperatingPoint { voltage: 12.5, current: 3.0 },
+                OperatingPoint { voltage: 15.0, current: 0.5 },
+            ),
+            "050010\r125030\r150005\rOK\r",
+            low_voltage_psu.val,
+        );
+    }
+
+    test serialize_for_high_voltage_psu(high_voltage_psu) {
+        let _e = expect_serializes_to(
+            Presets(
+                OperatingPoint { voltage: 5.0, current: 1.0 },
+                OperatingPoint { voltage: 12.5, current: 3.0 },
+                OperatingPoint { voltage: 15.0, current: 0.55 },
+            ),
+            "050100\r125300\r150055\rOK\r",
+            high_voltage_psu.val,
+        );
+    }
+
+    test round_trips(any_psu) {
+        let _e = expect_round_trips(
+            Presets(
+                OperatingPoint { voltage: 1.1, current: 0.2 },
+                OperatingPoint { voltage: 3.3, current: 0.4 },
+                OperatingPoint { voltage: 5.5, current: 0.6 },
+            ),
+            any_psu.val,
+        );
+    }
+
+    test cant_serialize_if_unrepresentable(any_psu) {
+        let zero = OperatingPoint { voltage: 0., current: 0. };
+        let bad = OperatingPoint { voltage: 100., current: 0. };
+
+        let _e = expect_cant_serialize(Presets(zero, zero, bad), any_psu.val);
+    }
 }
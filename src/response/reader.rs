@@ -0,0 +1,419 @@
+use crate::{
+    response::{
+        frame_end, parse_frame, Error, Response, ResponseSource, Result,
+    },
+    SupplyVariant,
+};
+
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for a response, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A buffered reader for supply responses.
+///
+/// Serial ports (and USB-CDC ports in particular) rarely deliver a response in
+/// a single read. This accumulates bytes from the underlying source until a
+/// full response has arrived, or until a deadline passes. Any bytes following
+/// the response are kept for the next call to
+/// [`get_response()`](ResponseReader::get_response).
+///
+/// Reads which fail with [`TimedOut`](io::ErrorKind::TimedOut),
+/// [`WouldBlock`](io::ErrorKind::WouldBlock), or
+/// [`Interrupted`](io::ErrorKind::Interrupted) are retried until the deadline.
+///
+/// # Errors
+///
+/// In addition to parsing errors, [`get_response()`] reports:
+///
+/// - [`NoResponse`](Error::NoResponse): Nothing arrived before the timeout
+/// - [`MalformedResponse`](Error::MalformedResponse): The response was
+///   incomplete when the timeout expired, or didn't match the expected format.
+///   In either case, the offending bytes are discarded.
+/// - [`ReadFailure`](Error::ReadFailure): The source reported any other error
+///
+/// [`get_response()`]: ResponseSource::get_response
+#[derive(Debug)]
+pub struct ResponseReader<S> {
+    source: S,
+    buf: Vec<u8>,
+    timeout: Duration,
+
+    /// What the most recent call to `get_response()` consumed.
+    last: Vec<u8>,
+
+    /// Whether a read has ever returned without data.
+    returned_idle: bool,
+}
+
+impl<S> ResponseReader<S> {
+    /// Wrap a source, using the [`DEFAULT_TIMEOUT`].
+    pub fn new(source: S) -> Self {
+        Self::with_timeout(source, DEFAULT_TIMEOUT)
+    }
+
+    /// Wrap a source, with a custom timeout.
+    pub fn with_timeout(source: S, timeout: Duration) -> Self {
+        ResponseReader {
+            source,
+            buf: Vec::new(),
+            timeout,
+            last: Vec::new(),
+            returned_idle: false,
+        }
+    }
+
+    /// How long to wait for a complete response.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Change how long to wait for a complete response.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Bytes which have been received, but not yet parsed.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// The bytes consumed by the most recent call to
+    /// [`get_response()`](ResponseSource::get_response).
+    ///
+    /// This is the complete response if one arrived, whether or not it could
+    /// be parsed, or whatever fragment was discarded if it didn't.
+    pub fn last_received(&self) -> &[u8] {
+        &self.last
+    }
+
+    /// Whether the source has been seen to return from a read with no data,
+    /// by timing out or otherwise.
+    ///
+    /// [`drain()`](ResponseReader::drain) reads until the source goes quiet,
+    /// and so blocks forever on a source whose reads don't return until data
+    /// arrives. Until this is `true`, the source may be one of those.
+    pub fn returns_when_idle(&self) -> bool {
+        self.returned_idle
+    }
+
+    /// Get a reference to the underlying source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Get a mutable reference to the underlying source.
+    ///
+    /// Reading directly from the source may lose part of a response.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Unwrap the source.
+    ///
+    /// Any buffered bytes are lost.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> ResponseSource for ResponseReader<S>
+where
+    S: io::Read,
+{
+    fn get_response<R: Response>(
+        &mut self,
+        variant: &SupplyVariant,
+    ) -> Result<R> {
+        let deadline = Instant::now() + self.timeout;
+        self.last.clear();
+
+        loop {
+            if let Some(end) = frame_end(&self.buf) {
+                self.last = self.buf.drain(..end).collect();
+
+                return parse_frame(&self.last, variant);
+            }
+
+            let filled = self.fill().inspect_err(|_| {
+                self.last = self.buf.clone();
+            })?;
+            if !filled || Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        if self.buf.is_empty() {
+            Err(Error::NoResponse)
+        } else {
+            self.last = std::mem::take(&mut self.buf);
+            Err(Error::MalformedResponse)
+        }
+    }
+}
+
+impl<S> ResponseReader<S>
+where
+    S: io::Read,
+{
+    /// Discard all buffered input, and anything else the source delivers
+    /// until it goes quiet.
+    ///
+    /// The source is considered quiet once a read returns nothing. Draining
+    /// gives up after the configured [`timeout()`](ResponseReader::timeout),
+    /// in case the source never stops talking.
+    ///
+    /// Returns the number of bytes discarded.
+    pub fn drain(&mut self) -> Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            let before = self.buf.len();
+            let open = self.fill()?;
+            if !open || self.buf.len() == before || Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        Ok(self.buf.drain(..).count())
+    }
+
+    /// Wait for a response terminator, then [`drain()`] everything.
+    ///
+    /// This realigns the reader with the start of a response, provided that
+    /// a response has been solicited: everything up to and including the last
+    /// terminator is stale, and anything after it is an incomplete fragment.
+    ///
+    /// Returns the number of bytes discarded, or
+    /// [`NoResponse`](Error::NoResponse) (having discarded everything) if no
+    /// terminator arrives before the timeout.
+    ///
+    /// [`drain()`]: ResponseReader::drain
+    pub fn realign(&mut self) -> Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+
+        while frame_end(&self.buf).is_none() {
+            if !self.fill()? || Instant::now() >= deadline {
+                self.buf.clear();
+                return Err(Error::NoResponse);
+            }
+        }
+
+        self.drain()
+    }
+
+    /// Read whatever is available from the source into the buffer.
+    ///
+    /// Returns `false` if the source has reached end-of-file.
+    fn fill(&mut self) -> Result<bool> {
+        let mut chunk = [0; 64];
+
+        match self.source.read(&mut chunk) {
+            Ok(0) => {
+                self.returned_idle = true;
+                Ok(false)
+            }
+            Ok(count) => {
+                self.buf.extend_from_slice(&chunk[..count]);
+                Ok(true)
+            }
+            Err(e) => match e.kind() {
+                io::ErrorKind::TimedOut => {
+                    self.returned_idle = true;
+                    Ok(true)
+                }
+                io::ErrorKind::Interrupted => Ok(true),
+                io::ErrorKind::WouldBlock => {
+                    self.returned_idle = true;
+                    // Nonblocking sources would otherwise spin.
+                    thread::sleep(Duration::from_millis(1));
+                    Ok(true)
+                }
+                _ => Err(e.into()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        response::{
+            test_util::{Chunked, Scripted},
+            Error::*,
+            Presets, Settings, Status,
+        },
+        test_util::{any_psu, low_voltage_psu},
+        OperatingPoint, OutputMode,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test reassembles_short_reads(low_voltage_psu, chunk_size) {
+        let raw = "050010\r125030\r150005\rOK\r".as_bytes();
+        let source = Chunked::new(raw, chunk_size.val);
+        let mut reader = ResponseReader::new(source);
+
+        let presets = reader.get_response(low_voltage_psu.val).unwrap();
+
+        expect_that!(&presets, eq(Presets(
+            OperatingPoint { voltage: 5.0, current: 1.0 },
+            OperatingPoint { voltage: 12.5, current: 3.0 },
+            OperatingPoint { voltage: 15.0, current: 0.5 },
+        )));
+    }
+
+    test keeps_leftover_bytes(low_voltage_psu) {
+        let raw = "123456\rOK\r030201451\rOK\rOK\r".as_bytes();
+        let mut reader = ResponseReader::new(raw);
+        let variant = low_voltage_psu.val;
+
+        let settings = reader.get_response(variant).unwrap();
+        expect_that!(&settings, eq(Settings { voltage: 12.3, current: 45.6 }));
+        expect_that!(&reader.buffer(), eq("030201451\rOK\rOK\r".as_bytes()));
+
+        let status = reader.get_response(variant).unwrap();
+        expect_that!(&status, eq(Status {
+            voltage: 3.02,
+            current: 1.45,
+            mode: OutputMode::ConstantCurrent,
+        }));
+
+        reader.get_response::<()>(variant).unwrap();
+        expect_that!(&reader.buffer().is_empty(), eq(true));
+    }
+
+    test waits_through_timeouts(any_psu) {
+        let source = Scripted::new(vec![
+            Err(io::ErrorKind::TimedOut),
+            Ok("O"),
+            Err(io::ErrorKind::WouldBlock),
+            Err(io::ErrorKind::Interrupted),
+            Ok("K\r"),
+        ]);
+        let mut reader = ResponseReader::new(source);
+
+        reader.get_response::<()>(any_psu.val).unwrap();
+    }
+
+    test notices_idle_source(any_psu) {
+        let source = Scripted::new(vec![Ok("OK\r")]);
+        let mut reader = ResponseReader::new(source);
+
+        reader.get_response::<()>(any_psu.val).unwrap();
+        expect_that!(&reader.returns_when_idle(), eq(false));
+
+        reader.drain().unwrap();
+        expect_that!(&reader.returns_when_idle(), eq(true));
+    }
+
+    test no_response_after_timeout(any_psu) {
+        let source = Scripted::new(vec![]);
+        let timeout = Duration::from_millis(10);
+        let mut reader = ResponseReader::with_timeout(source, timeout);
+
+        let start = Instant::now();
+        let err = reader.get_response::<()>(any_psu.val).unwrap_err();
+
+        assert_that!(&err, is_variant!(NoResponse));
+        assert_that!(&(start.elapsed() >= timeout), eq(true));
+    }
+
+    test partial_response_after_timeout(any_psu) {
+        let source = Scripted::new(vec![Ok("123")]);
+        let timeout = Duration::from_millis(10);
+        let mut reader = ResponseReader::with_timeout(source, timeout);
+
+        let err = reader.get_response::<Settings>(any_psu.val).unwrap_err();
+
+        assert_that!(&err, is_variant!(MalformedResponse));
+        expect_that!(&reader.buffer().is_empty(), eq(true));
+    }
+
+    test partial_response_at_eof(any_psu) {
+        let mut reader = ResponseReader::new("123\r".as_bytes());
+
+        let err = reader.get_response::<Settings>(any_psu.val).unwrap_err();
+
+        assert_that!(&err, is_variant!(MalformedResponse));
+    }
+
+    test discards_mismatched_response(any_psu) {
+        let mut reader = ResponseReader::new("OK\rOK\r".as_bytes());
+        let variant = any_psu.val;
+
+        let err = reader.get_response::<Settings>(variant).unwrap_err();
+        assert_that!(&err, has_structure!(WrongLength {
+            expected: eq(10),
+            actual: eq(3)
+        }));
+
+        reader.get_response::<()>(variant).unwrap();
+    }
+
+    test drains_stale_input(any_psu) {
+        let source = Scripted::new(vec![Ok("456\rOK\r12"), Ok("3\rO")]);
+        let mut reader = ResponseReader::new(source);
+
+        let err = reader.get_response::<()>(any_psu.val).unwrap_err();
+        assert_that!(&err, is_variant!(WrongLength));
+        expect_that!(&reader.buffer(), eq("12".as_bytes()));
+
+        expect_that!(&reader.drain().unwrap(), eq(5));
+        expect_that!(&reader.buffer().is_empty(), eq(true));
+    }
+
+    test realigns_on_terminator(any_psu) {
+        let source = Scripted::new(vec![
+            Ok("3\rOK"),
+            Err(io::ErrorKind::TimedOut),
+            Ok("\r030201451\rOK\r0"),
+        ]);
+        let mut reader = ResponseReader::new(source);
+
+        expect_that!(&reader.realign().unwrap(), eq(19));
+        expect_that!(&reader.buffer().is_empty(), eq(true));
+    }
+
+    test realign_needs_terminator(any_psu) {
+        let source = Scripted::new(vec![Ok("030201451")]);
+        let timeout = Duration::from_millis(10);
+        let mut reader = ResponseReader::with_timeout(source, timeout);
+
+        let err = reader.realign().unwrap_err();
+
+        assert_that!(&err, is_variant!(NoResponse));
+        expect_that!(&reader.buffer().is_empty(), eq(true));
+    }
+
+    test propagates_io_error(any_psu) {
+        let source = Scripted::new(vec![
+            Ok("12"),
+            Err(io::ErrorKind::BrokenPipe),
+        ]);
+        let mut reader = ResponseReader::new(source);
+
+        let err = reader.get_response::<Settings>(any_psu.val).unwrap_err();
+
+        assert_that!(&err, is_variant!(ReadFailure));
+    }
+
+    fixture chunk_size(size: usize) -> usize {
+        params {
+            vec![1, 2, 7, 13, 23].into_iter()
+        }
+        setup(&mut self) {
+            *self.size
+        }
+    }
+}
@@ -3,6 +3,8 @@ use crate::{
     ArgFormat, SupplyVariant,
 };
 
+use std::io;
+
 /// The power supply's output settings.
 ///
 /// This is the response format used by the
@@ -32,11 +34,29 @@ impl Response for Settings {
         };
 
         let (volt_raw, curr_raw) = raw.split_at(volt_fmt.digits);
-        let voltage = volt_fmt.parse(volt_raw)?;
-        let current = curr_fmt.parse(curr_raw)?;
+        let voltage = volt_fmt.parse(volt_raw, "voltage")?;
+        let current = curr_fmt.parse(curr_raw, "current")?;
 
         Ok(Settings { voltage, current })
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        let volt_fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: 3,
+        };
+        let curr_fmt = ArgFormat {
+            decimals: variant.current_decimals,
+            digits: 3,
+        };
+
+        volt_fmt.serialize(sink, self.voltage)?;
+        curr_fmt.serialize(sink, self.current)
+    }
 }
 
 #[cfg(test)]
@@ -47,8 +67,12 @@ galvanic_test::test_suite! {
 
     use crate::{
         response::{
-            test_util::{expect_deserialize_error, expect_deserializes_to},
-            Error::MalformedResponse,
+            test_util::{
+                expect_cant_serialize, expect_deserialize_error,
+                expect_deserializes_to, expect_round_trips,
+                expect_serializes_to, invalid_field,
+            },
+            Error::WrongLength,
         },
         test_util::{any_psu, high_voltage_psu, low_voltage_psu},
     };
@@ -162,20 +186,55 @@ galvanic_test::test_suite! {
     test fails_to_parse_invalid_settings(any_psu) {
         let _e = expect_deserialize_error::<Settings>(
             "x00000\rOK\r",
-            MalformedResponse,
+            invalid_field("voltage", "x00"),
             any_psu.val,
         );
 
         let _e = expect_deserialize_error::<Settings>(
             "000x00\rOK\r",
-            MalformedResponse,
+            invalid_field("current", "x00"),
             any_psu.val,
         );
 
         let _e = expect_deserialize_error::<Settings>(
             "1234567\rOK\r",
-            MalformedResponse,
+            WrongLength { expected: 10, actual: 11 },
             any_psu.val,
         );
     }
+
+    test serialize_for_low_voltage(low_voltage_psu) {
+        let _e = expect_serializes_to(
+            Settings { voltage: 12.3, current: 4.5 },
+            "123045\rOK\r",
+            low_voltage_psu.val,
+        );
+    }
+
+    test serialize_for_high_voltage(high_voltage_psu) {
+        let _e = expect_serializes_to(
+            Settings { voltage: 12.3, current: 4.5 },
+            "123450\rOK\r",
+            high_voltage_psu.val,
+        );
+    }
+
+    test round_trips(any_psu) {
+        let _e = expect_round_trips(
+            Settings { voltage: 5.5, current: 1.2 },
+            any_psu.val,
+        );
+    }
+
+    test cant_serialize_if_unrepresentable(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_serialize(
+            Settings { voltage: 100., current: 0. },
+            variant,
+        );
+        let _e = expect_cant_serialize(
+            Settings { voltage: 0., current: -1. },
+            variant,
+        );
+    }
 }
@@ -1,8 +1,13 @@
 use crate::{
-    response::{Error::MalformedResponse, Response, Result},
+    response::{
+        Error::{InvalidField, MalformedResponse, WriteFailure},
+        FieldBytes, Response, Result,
+    },
     ArgFormat, OutputMode, SupplyVariant,
 };
 
+use std::io;
+
 /// The supply's instantaneous state.
 ///
 /// This is the response format used by the
@@ -38,12 +43,17 @@ impl Response for Status {
             raw.split_last().ok_or(MalformedResponse)?;
         let (volt_raw, curr_raw) = args_raw.split_at(arg_fmt.digits);
 
-        let voltage = arg_fmt.parse(volt_raw)?;
-        let current = arg_fmt.parse(curr_raw)?;
+        let voltage = arg_fmt.parse(volt_raw, "voltage")?;
+        let current = arg_fmt.parse(curr_raw, "current")?;
         let mode = match mode_raw {
             b'0' => OutputMode::ConstantVoltage,
             b'1' => OutputMode::ConstantCurrent,
-            _ => return Err(MalformedResponse),
+            _ => {
+                return Err(InvalidField {
+                    field: "mode",
+                    raw: FieldBytes::new(&[mode_raw]),
+                })
+            }
         };
 
         Ok(Status {
@@ -52,6 +62,26 @@ impl Response for Status {
             mode,
         })
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        _variant: &SupplyVariant,
+    ) -> Result<()> {
+        let arg_fmt = ArgFormat {
+            decimals: 2,
+            digits: 4,
+        };
+
+        arg_fmt.serialize(sink, self.voltage)?;
+        arg_fmt.serialize(sink, self.current)?;
+        let mode_raw: &[u8] = match self.mode {
+            OutputMode::ConstantVoltage => b"0",
+            OutputMode::ConstantCurrent => b"1",
+        };
+
+        sink.write_all(mode_raw).map_err(WriteFailure)
+    }
 }
 
 #[cfg(test)]
@@ -62,7 +92,9 @@ galvanic_test::test_suite! {
 
     use crate::{
         response::test_util::{
-            expect_deserialize_error, expect_deserializes_to,
+            expect_cant_serialize, expect_deserialize_error,
+            expect_deserializes_to, expect_round_trips, expect_serializes_to,
+            invalid_field,
         },
         test_util::any_psu,
     };
@@ -136,58 +168,101 @@ galvanic_test::test_suite! {
     test fails_to_parse_bad_param(any_psu) {
         let _e = expect_deserialize_error::<Status>(
             "foo000000\rOK\r",
-            MalformedResponse,
+            invalid_field("voltage", "foo0"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "----00001\rOK\r",
-            MalformedResponse,
+            invalid_field("voltage", "----"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "OK0000000\rOK\r",
-            MalformedResponse,
+            invalid_field("voltage", "OK00"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "0000foo00\rOK\r",
-            MalformedResponse,
+            invalid_field("current", "foo0"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "0000----1\rOK\r",
-            MalformedResponse,
+            invalid_field("current", "----"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "0000OK000\rOK\r",
-            MalformedResponse,
+            invalid_field("current", "OK00"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "0000blah1\rOK\r",
-            MalformedResponse,
+            invalid_field("current", "blah"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "123456782\rOK\r",
-            MalformedResponse,
+            invalid_field("mode", "2"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "888776543\rOK\r",
-            MalformedResponse,
+            invalid_field("mode", "3"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "000000009\rOK\r",
-            MalformedResponse,
+            invalid_field("mode", "9"),
             any_psu.val
         );
         let _e = expect_deserialize_error::<Status>(
             "00000000X\rOK\r",
-            MalformedResponse,
+            invalid_field("mode", "X"),
             any_psu.val
         );
     }
+
+    test can_serialize(any_psu) {
+        let _e = expect_serializes_to(
+            Status {
+                voltage: 3.02,
+                current: 1.45,
+                mode: OutputMode::ConstantCurrent,
+            },
+            "030201451\rOK\r",
+            any_psu.val,
+        );
+        let _e = expect_serializes_to(
+            Status {
+                voltage: 99.99,
+                current: 0.,
+                mode: OutputMode::ConstantVoltage,
+            },
+            "999900000\rOK\r",
+            any_psu.val,
+        );
+    }
+
+    test round_trips(any_psu) {
+        let _e = expect_round_trips(
+            Status {
+                voltage: 12.34,
+                current: 5.67,
+                mode: OutputMode::ConstantVoltage,
+            },
+            any_psu.val,
+        );
+    }
+
+    test cant_serialize_if_unrepresentable(any_psu) {
+        let _e = expect_cant_serialize(
+            Status {
+                voltage: 100.,
+                current: 0.,
+                mode: OutputMode::ConstantVoltage,
+            },
+            any_psu.val,
+        );
+    }
 }
@@ -1,27 +1,29 @@
 use crate::{
-    response::{Error, Response, ResponseSource},
+    response::{Error, FieldBytes, Response, ResponseReader, ResponseSource},
     SupplyVariant,
 };
 
 use galvanic_assert::{
-    assert_that, get_expectation_for, is_variant, matchers::*, Expectation,
+    assert_that, get_expectation_for, has_structure, is_variant, matchers::*,
+    structure, Expectation,
 };
 use galvanic_test::fixture;
 
-use std::{fmt::Debug, io, io::Read};
+use std::{fmt::Debug, io, io::Read, time::Duration};
 
 pub fn expect_deserializes_to<R: Response + Debug>(
     resp: &str,
     expected_result: R,
     variant: &SupplyVariant,
 ) -> Expectation {
-    let mut source = dbg!(resp).as_bytes();
+    let mut source = ResponseReader::new(dbg!(resp).as_bytes());
 
     let result = dbg!(source.get_response(variant)).unwrap();
 
     let byte: &mut [u8] = &mut [0; 1];
     assert_that!(
-        source.read(byte).unwrap() == 0, otherwise "Not all bytes parsed"
+        source.buffer().is_empty() && source.get_mut().read(byte).unwrap() == 0,
+        otherwise "Not all bytes parsed"
     );
 
     get_expectation_for!(&result, eq(expected_result))
@@ -35,17 +37,64 @@ pub fn assert_deserializes_to<R: Response + Debug>(
     expect_deserializes_to(resp, expected_result, variant).verify();
 }
 
+pub fn expect_serializes_to<R: Response + Debug>(
+    resp: R,
+    expected_raw: &str,
+    variant: &SupplyVariant,
+) -> Expectation {
+    let mut sink = Vec::new();
+    resp.serialize(&mut sink, variant).unwrap();
+
+    let written = std::str::from_utf8(&sink).unwrap().to_owned();
+    get_expectation_for!(&written, eq(expected_raw.to_owned()))
+}
+
+pub fn expect_cant_serialize<R: Response + Debug>(
+    resp: R,
+    variant: &SupplyVariant,
+) -> Expectation {
+    let result = resp.serialize(&mut Vec::new(), variant);
+
+    get_expectation_for!(
+        &result,
+        has_structure!(Err[is_variant!(Error::ValueUnrepresentable)])
+    )
+}
+
+pub fn expect_round_trips<R: Response + Debug + Clone + 'static>(
+    resp: R,
+    variant: &SupplyVariant,
+) -> Expectation {
+    let mut raw = Vec::new();
+    resp.serialize(&mut raw, variant).unwrap();
+    let parsed = ResponseReader::new(raw.as_slice())
+        .get_response::<R>(variant)
+        .unwrap();
+
+    get_expectation_for!(&parsed, eq(resp))
+}
+
 pub fn expect_deserialize_error_from<R: Response + Debug, S: io::Read>(
     source: &mut S,
     expected_error: Error,
     variant: &SupplyVariant,
 ) -> Expectation {
+    // Long enough for an in-memory source, but short enough that retried
+    // errors don't stall the tests
+    let mut source =
+        ResponseReader::with_timeout(source, Duration::from_millis(10));
     let err = dbg!(source.get_response::<R>(variant)).unwrap_err();
 
     match expected_error {
         Error::MalformedResponse => {
             get_expectation_for!(&err, is_variant!(Error::MalformedResponse))
         }
+        Error::WrongLength { .. } | Error::InvalidField { .. } => {
+            get_expectation_for!(
+                &format!("{:?}", err),
+                eq(format!("{:?}", expected_error))
+            )
+        }
         Error::NoResponse => {
             get_expectation_for!(&err, is_variant!(Error::NoResponse))
         }
@@ -56,6 +105,7 @@ pub fn expect_deserialize_error_from<R: Response + Debug, S: io::Read>(
                 get_expectation_for!(false, otherwise "not a read failure")
             }
         }
+        _ => panic!("not a deserialization error: {:?}", expected_error),
     }
 }
 
@@ -69,6 +119,14 @@ pub fn expect_deserialize_error<R: Response + Debug>(
     expect_deserialize_error_from::<R, _>(&mut source, expected_error, variant)
 }
 
+/// The error for a field which couldn't be parsed.
+pub fn invalid_field(field: &'static str, raw: &str) -> Error {
+    Error::InvalidField {
+        field,
+        raw: FieldBytes::new(raw.as_bytes()),
+    }
+}
+
 pub fn assert_deserialize_error<R: Response + Debug>(
     resp: &str,
     expected_error: Error,
@@ -123,7 +181,7 @@ fixture! {
         }
         setup(&mut self) {
             let raw = *self.string;
-            let num = usize::from_str_radix(raw, 10).unwrap();
+            let num = raw.parse::<usize>().unwrap();
             let one_decimal = (num as f32) / 10.;
             let two_decimals = (num as f32) / 100.;
 
@@ -223,3 +281,54 @@ where
         })
     }
 }
+
+/// A source which never returns more than `chunk` bytes from a single read.
+pub struct Chunked<S> {
+    chunk: usize,
+    source: S,
+}
+
+impl<S> Chunked<S> {
+    pub fn new(source: S, chunk: usize) -> Self {
+        Chunked { chunk, source }
+    }
+}
+
+impl<S> Read for Chunked<S>
+where
+    S: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk);
+        self.source.read(&mut buf[..len])
+    }
+}
+
+/// A source which plays back a fixed series of reads.
+///
+/// Once the script is exhausted, every read times out.
+pub struct Scripted {
+    events: std::collections::VecDeque<Result<&'static str, io::ErrorKind>>,
+}
+
+impl Scripted {
+    pub fn new(events: Vec<Result<&'static str, io::ErrorKind>>) -> Self {
+        Scripted {
+            events: events.into(),
+        }
+    }
+}
+
+impl Read for Scripted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.events.pop_front() {
+            Some(Ok(data)) => {
+                let data = data.as_bytes();
+                buf[..data.len()].copy_from_slice(data);
+                Ok(data.len())
+            }
+            Some(Err(kind)) => Err(kind.into()),
+            None => Err(io::ErrorKind::TimedOut.into()),
+        }
+    }
+}
@@ -3,6 +3,8 @@ use crate::{
     ArgFormat, SupplyVariant,
 };
 
+use std::io;
+
 /// A supply voltage.
 ///
 /// This is the response format used by the
@@ -21,10 +23,23 @@ impl Response for Voltage {
             digits: Self::arg_bytes(),
         };
 
-        let voltage = voltage_fmt.parse(raw)?;
+        let voltage = voltage_fmt.parse(raw, "voltage")?;
 
         Ok(Voltage(voltage))
     }
+
+    fn serialize_args<S: io::Write>(
+        &self,
+        sink: &mut S,
+        variant: &SupplyVariant,
+    ) -> Result<()> {
+        let voltage_fmt = ArgFormat {
+            decimals: variant.voltage_decimals,
+            digits: Self::arg_bytes(),
+        };
+
+        voltage_fmt.serialize(sink, self.0)
+    }
 }
 
 impl From<f32> for Voltage {
@@ -42,8 +57,10 @@ galvanic_test::test_suite! {
     use crate::{
         response::{
             test_util::{
-                assert_deserialize_error, assert_deserializes_to, invalid_num,
-                valid_ack, valid_num, valid_sep,
+                assert_deserialize_error, assert_deserializes_to,
+                expect_cant_serialize, expect_round_trips,
+                expect_serializes_to, invalid_field, invalid_num, valid_ack,
+                valid_num, valid_sep,
             },
             Error,
         },
@@ -66,13 +83,35 @@ galvanic_test::test_suite! {
         invalid_num,
         valid_ack
     ) {
-        let mut resp = invalid_num.val.to_owned();
+        let num = invalid_num.val;
+        let mut resp = num.to_owned();
         resp.push('\r');
         resp.push_str(valid_ack.val);
-        assert_deserialize_error::<Voltage>(
-            &resp,
-            Error::MalformedResponse,
-            any_psu.val
-        );
+        // A response ends at its first terminator, even one inside the field
+        let framed = resp.find(valid_ack.val).unwrap() + valid_ack.val.len();
+        let expected = if framed == 7 {
+            invalid_field("voltage", num)
+        } else {
+            Error::WrongLength { expected: 7, actual: framed }
+        };
+        assert_deserialize_error::<Voltage>(&resp, expected, any_psu.val);
+    }
+
+    test can_serialize(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_serializes_to(Voltage(12.3), "123\rOK\r", variant);
+        let _e = expect_serializes_to(Voltage(0.), "000\rOK\r", variant);
+        let _e = expect_serializes_to(Voltage(99.9), "999\rOK\r", variant);
+    }
+
+    test round_trips(any_psu, valid_num) {
+        let _e = expect_round_trips(Voltage(valid_num.val.one_decimal), any_psu.val);
+    }
+
+    test cant_serialize_if_unrepresentable(any_psu) {
+        let variant = any_psu.val;
+        let _e = expect_cant_serialize(Voltage(100.), variant);
+        let _e = expect_cant_serialize(Voltage(-1.), variant);
+        let _e = expect_cant_serialize(Voltage(f32::NAN), variant);
     }
 }
@@ -0,0 +1,7 @@
+//! Emulated supplies, for testing without hardware
+
+mod faulty;
+mod load;
+mod virtual_supply;
+
+pub use self::{faulty::*, load::*, virtual_supply::*};
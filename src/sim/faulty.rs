@@ -0,0 +1,453 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long a read blocks while waiting for a delayed response.
+///
+/// Like a serial port's read timeout, the read then fails with
+/// [`TimedOut`](io::ErrorKind::TimedOut).
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Something that can go wrong while exchanging a single command.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Fault {
+    /// The response is never sent.
+    Drop,
+
+    /// The response is held back for a while.
+    Delay(Duration),
+
+    /// Only this many bytes of the response are sent.
+    Truncate(usize),
+
+    /// The response is sent twice.
+    Duplicate,
+
+    /// The response byte at this offset is replaced with garbage.
+    ///
+    /// Offsets beyond the end of the response have no effect.
+    Corrupt(usize),
+
+    /// Writing the command fails with this error, and it never reaches the
+    /// supply.
+    WriteError(io::ErrorKind),
+
+    /// Reading the response fails with this error once, before the response
+    /// is delivered.
+    ReadError(io::ErrorKind),
+}
+
+/// A wrapper which makes an emulated supply misbehave.
+///
+/// Faults are scheduled against commands, which are numbered from zero in the
+/// order they're written:
+///
+/// ```
+/// use bk168xb::{
+///     response::Error::NoResponse,
+///     sim::{Fault, Faulty, VirtualSupply},
+///     supply, Supply, BK1687B,
+/// };
+///
+/// let mut port = Faulty::new(VirtualSupply::new(BK1687B));
+/// port.inject(1, Fault::Drop);
+/// let mut supply = Supply::new(port, BK1687B);
+///
+/// assert!(supply.status().is_ok());
+/// assert!(matches!(
+///     supply.status(),
+///     Err(supply::Error::Response {
+///         source: NoResponse,
+///         ..
+///     })
+/// ));
+/// assert!(supply.status().is_ok());
+/// ```
+///
+/// The wrapped supply must respond to each command as soon as it's written,
+/// and report end-of-file once its response has been read -- as
+/// [`VirtualSupply`](crate::sim::VirtualSupply) does.
+#[derive(Debug)]
+pub struct Faulty<S> {
+    inner: S,
+    faults: Vec<(usize, Fault)>,
+    stop_after: Option<usize>,
+
+    /// How many complete commands have been written.
+    commands: usize,
+
+    /// A partially-written command.
+    rx: Vec<u8>,
+
+    /// Responses waiting to be read.
+    tx: VecDeque<Reply>,
+}
+
+/// A single (possibly mangled) response.
+#[derive(Debug)]
+struct Reply {
+    ready: Instant,
+    error: Option<io::ErrorKind>,
+    bytes: VecDeque<u8>,
+}
+
+impl<S> Faulty<S> {
+    /// Wrap a supply, which initially behaves perfectly.
+    pub fn new(inner: S) -> Self {
+        Faulty {
+            inner,
+            faults: Vec::new(),
+            stop_after: None,
+            commands: 0,
+            rx: Vec::new(),
+            tx: VecDeque::new(),
+        }
+    }
+
+    /// Schedule a fault for the `command`th command.
+    ///
+    /// Several faults may be scheduled for the same command; they're applied
+    /// in the order they were injected.
+    pub fn inject(&mut self, command: usize, fault: Fault) {
+        self.faults.push((command, fault));
+    }
+
+    /// Stop responding after `count` commands have been answered.
+    ///
+    /// Subsequent commands are discarded without reaching the supply, as if
+    /// it had been unplugged.
+    pub fn stop_after(&mut self, count: usize) {
+        self.stop_after = Some(count);
+    }
+
+    /// How many commands have been written so far.
+    pub fn commands(&self) -> usize {
+        self.commands
+    }
+
+    /// Get a reference to the wrapped supply.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped supply.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Unwrap the supply.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn faults_for(&self, command: usize) -> impl Iterator<Item = Fault> + '_ {
+        self.faults
+            .iter()
+            .filter(move |(index, _)| *index == command)
+            .map(|(_, fault)| *fault)
+    }
+}
+
+impl<S: Read + Write> Faulty<S> {
+    /// Pass a complete command to the supply, and queue its response.
+    fn exchange(&mut self, command: &[u8]) -> io::Result<()> {
+        let index = self.commands;
+        self.commands += 1;
+
+        let write_error =
+            self.faults_for(index).find_map(|fault| match fault {
+                Fault::WriteError(kind) => Some(kind),
+                _ => None,
+            });
+        if let Some(kind) = write_error {
+            return Err(kind.into());
+        }
+
+        if self.stop_after.is_some_and(|count| index >= count) {
+            return Ok(());
+        }
+
+        self.inner.write_all(command)?;
+        self.inner.flush()?;
+
+        let mut response = Vec::new();
+        self.inner.read_to_end(&mut response)?;
+
+        let mut reply = Reply {
+            ready: Instant::now(),
+            error: None,
+            bytes: response.into(),
+        };
+        let mut copies = 1;
+
+        for fault in self.faults_for(index) {
+            match fault {
+                Fault::Drop => copies = 0,
+                Fault::Delay(delay) => reply.ready += delay,
+                Fault::Truncate(len) => reply.bytes.truncate(len),
+                Fault::Duplicate => copies += 1,
+                Fault::Corrupt(offset) => {
+                    if let Some(byte) = reply.bytes.get_mut(offset) {
+                        *byte = b'?';
+                    }
+                }
+                Fault::ReadError(kind) => reply.error = Some(kind),
+                Fault::WriteError(_) => {}
+            }
+        }
+
+        for copy in 0..copies {
+            self.tx.push_back(Reply {
+                ready: reply.ready,
+                error: if copy == 0 { reply.error } else { None },
+                bytes: reply.bytes.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<S> Read for Faulty<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // A reply truncated to nothing mustn't look like end-of-file while
+        // others are still queued behind it.
+        while self.tx.front().is_some_and(|reply| {
+            reply.bytes.is_empty() && reply.error.is_none()
+        }) {
+            self.tx.pop_front();
+        }
+
+        let reply = match self.tx.front_mut() {
+            Some(reply) => reply,
+            None => return Ok(0),
+        };
+
+        let now = Instant::now();
+        if now < reply.ready {
+            thread::sleep(POLL_INTERVAL.min(reply.ready - now));
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        if let Some(kind) = reply.error.take() {
+            return Err(kind.into());
+        }
+
+        let count = reply.bytes.read(buf)?;
+        if reply.bytes.is_empty() {
+            self.tx.pop_front();
+        }
+
+        Ok(count)
+    }
+}
+
+impl<S: Read + Write> Write for Faulty<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.rx.push(byte);
+            if byte != b'\r' {
+                continue;
+            }
+
+            let command = std::mem::take(&mut self.rx);
+            self.exchange(&command)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command,
+        response::{
+            self,
+            Error::{
+                InvalidField, MalformedResponse, NoResponse, ReadFailure,
+                WrongLength,
+            },
+            FieldBytes,
+        },
+        sim::VirtualSupply,
+        supply::{Error, Supply},
+        test_util::any_psu,
+        OutputState,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    fn supply(
+        port: Faulty<VirtualSupply>,
+    ) -> Supply<Faulty<VirtualSupply>> {
+        let variant = port.get_ref().variant();
+        let mut supply = Supply::new(port, variant);
+        supply.set_timeout(Duration::from_millis(50));
+
+        supply
+    }
+
+    fn response_error<T: std::fmt::Debug>(
+        result: Result<T, Error>,
+    ) -> response::Error {
+        match result.unwrap_err() {
+            Error::Response { source, .. } => source,
+            err => panic!("not a response error: {:?}", err),
+        }
+    }
+
+    test behaves_without_faults(any_psu) {
+        let mut supply = supply(Faulty::new(VirtualSupply::new(any_psu.val)));
+
+        supply.set_voltage(5.).unwrap();
+        expect_that!(&supply.settings().unwrap().voltage, eq(5.));
+        expect_that!(&supply.get_ref().commands(), eq(2));
+    }
+
+    test drops_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        let mut supply = supply(port);
+
+        let err = response_error(supply.status());
+        assert_that!(&err, is_variant!(NoResponse));
+
+        supply.status().unwrap();
+    }
+
+    test delays_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Delay(Duration::from_millis(20)));
+        port.inject(1, Fault::Delay(Duration::from_millis(200)));
+        let mut supply = supply(port);
+
+        supply.status().unwrap();
+
+        let err = response_error(supply.status());
+        assert_that!(&err, is_variant!(NoResponse));
+    }
+
+    test truncates_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Truncate(4));
+        let mut supply = supply(port);
+
+        let err = response_error(supply.status());
+        assert_that!(&err, is_variant!(MalformedResponse));
+    }
+
+    test skips_empty_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Truncate(0));
+        port.write_all(b"GETD\rGETS\r").unwrap();
+
+        let mut received = Vec::new();
+        port.read_to_end(&mut received).unwrap();
+
+        // The settings response, rather than an early end-of-file
+        expect_that!(&received.len(), eq(10));
+    }
+
+    test duplicates_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(1, Fault::Duplicate);
+        let mut supply = supply(port);
+
+        // Framing errors are only resynced automatically once the port has
+        // shown that its reads return when there's nothing to read
+        supply.resync().unwrap();
+        supply.status().unwrap();
+
+        // The extra status response is mistaken for the settings response
+        let err = response_error(supply.settings());
+        assert_that!(&err, has_structure!(WrongLength {
+            expected: eq(10),
+            actual: eq(13)
+        }));
+
+        // ...but the real settings response is drained, rather than being
+        // mistaken for the next status response
+        supply.status().unwrap();
+    }
+
+    test resyncs_after_late_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Delay(Duration::from_millis(80)));
+        let mut supply = supply(port);
+
+        let err = response_error(supply.status());
+        assert_that!(&err, is_variant!(NoResponse));
+
+        supply.resync().unwrap();
+
+        supply.settings().unwrap();
+        expect_that!(&supply.get_ref().commands(), eq(3));
+    }
+
+    test corrupts_response(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Corrupt(0));
+        let mut supply = supply(port);
+
+        let err = response_error(supply.status());
+        assert_that!(&err, has_structure!(InvalidField {
+            field: eq("voltage"),
+            raw: eq(FieldBytes::new(b"?000"))
+        }));
+    }
+
+    test fails_read(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::ReadError(io::ErrorKind::BrokenPipe));
+        let mut supply = supply(port);
+
+        let err = response_error(supply.status());
+        assert_that!(&err, is_variant!(ReadFailure));
+    }
+
+    test fails_write(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::WriteError(io::ErrorKind::BrokenPipe));
+        let mut supply = supply(port);
+
+        let err = supply.set_output(OutputState::On).unwrap_err();
+        assert_that!(&err, has_structure!(Error::Command {
+            source: is_variant!(command::Error::WriteFailure)
+        }));
+        expect_that!(
+            &supply.get_ref().get_ref().state().output,
+            eq(OutputState::Off)
+        );
+    }
+
+    test stops_answering(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.stop_after(2);
+        let mut supply = supply(port);
+
+        supply.set_voltage(1.).unwrap();
+        supply.set_voltage(2.).unwrap();
+
+        let err = response_error(supply.set_voltage(3.));
+        assert_that!(&err, is_variant!(NoResponse));
+        expect_that!(
+            &supply.get_ref().get_ref().state().settings.voltage,
+            eq(2.)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt::Debug;
+
+/// Something connected to an emulated supply's output.
+///
+/// A load is described by how much current it draws at a given voltage. The
+/// supply uses this to decide whether it can hold its voltage setpoint, or
+/// whether it must drop into constant-current mode.
+pub trait Load: Debug + Send {
+    /// The current drawn (in Amps) when `voltage` is applied across the load.
+    ///
+    /// This must never decrease as `voltage` increases. It may return
+    /// infinity, if the load would draw unlimited current.
+    fn current(&self, voltage: f32) -> f32;
+}
+
+impl<L: Load + ?Sized> Load for Box<L> {
+    fn current(&self, voltage: f32) -> f32 {
+        (**self).current(voltage)
+    }
+}
+
+/// Nothing connected at all.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Open;
+
+impl Load for Open {
+    fn current(&self, _voltage: f32) -> f32 {
+        0.
+    }
+}
+
+/// A dead short across the output.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Short;
+
+impl Load for Short {
+    fn current(&self, voltage: f32) -> f32 {
+        if voltage > 0. {
+            f32::INFINITY
+        } else {
+            0.
+        }
+    }
+}
+
+/// A resistor, with the given resistance in Ohms.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Resistor(pub f32);
+
+impl Load for Resistor {
+    fn current(&self, voltage: f32) -> f32 {
+        voltage / self.0
+    }
+}
+
+/// An ideal constant-current sink, drawing the given current in Amps.
+///
+/// Like a real electronic load, it draws its full current at any non-zero
+/// voltage.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ConstantCurrent(pub f32);
+
+impl Load for ConstantCurrent {
+    fn current(&self, voltage: f32) -> f32 {
+        if voltage > 0. {
+            self.0
+        } else {
+            0.
+        }
+    }
+}
+
+/// A forward-biased diode, following the Shockley diode equation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Diode {
+    /// Reverse-bias saturation current, in Amps.
+    pub saturation_current: f32,
+
+    /// Ideality factor, typically between 1 and 2.
+    pub ideality: f32,
+}
+
+impl Diode {
+    /// Thermal voltage at room temperature, in Volts.
+    pub const THERMAL_VOLTAGE: f32 = 0.025_85;
+
+    /// A typical small-signal silicon diode, like a 1N4148.
+    pub fn silicon() -> Self {
+        Diode {
+            saturation_current: 4e-9,
+            ideality: 1.9,
+        }
+    }
+}
+
+impl Load for Diode {
+    fn current(&self, voltage: f32) -> f32 {
+        let exponent = voltage / (self.ideality * Self::THERMAL_VOLTAGE);
+
+        self.saturation_current * exponent.exp_m1()
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use galvanic_assert::{
+        expect_that, get_expectation_for, matchers::*,
+    };
+
+    test open_draws_nothing() {
+        expect_that!(&Open.current(0.), eq(0.));
+        expect_that!(&Open.current(30.), eq(0.));
+    }
+
+    test short_draws_unlimited_current() {
+        expect_that!(&Short.current(0.), eq(0.));
+        expect_that!(&Short.current(0.01), eq(f32::INFINITY));
+    }
+
+    test resistor_follows_ohms_law() {
+        let load = Resistor(10.);
+
+        expect_that!(&load.current(0.), eq(0.));
+        expect_that!(&load.current(5.), eq(0.5));
+    }
+
+    test constant_current_draws_fixed_current() {
+        let load = ConstantCurrent(1.5);
+
+        expect_that!(&load.current(0.), eq(0.));
+        expect_that!(&load.current(0.1), eq(1.5));
+        expect_that!(&load.current(20.), eq(1.5));
+    }
+
+    test diode_conducts_above_knee() {
+        let load = Diode::silicon();
+
+        expect_that!(&load.current(0.), eq(0.));
+        expect_that!(&load.current(0.3), lt(0.001));
+        expect_that!(&load.current(1.2), gt(1.));
+    }
+}
@@ -0,0 +1,620 @@
+use crate::{
+    command::{
+        AnyCommand, Decoder, SelectPreset, SetCurrent, SetCurrentLimit,
+        SetOutput, SetPresets, SetVoltage, SetVoltageLimit,
+    },
+    response::{
+        AnyResponse, Capabilities, Current, Presets, Settings, Status, Voltage,
+    },
+    sim::{Load, Open},
+    OperatingPoint, OutputMode, OutputState, SupplyVariant,
+};
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+/// The user-visible state of an emulated supply.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct State {
+    /// Voltage and current setpoints.
+    pub settings: OperatingPoint,
+
+    /// The "soft" voltage limit.
+    pub voltage_limit: f32,
+
+    /// The "soft" current limit.
+    pub current_limit: f32,
+
+    /// Stored operating points.
+    pub presets: Presets,
+
+    /// Whether the output is enabled.
+    pub output: OutputState,
+}
+
+impl State {
+    /// The state of a freshly-configured supply.
+    ///
+    /// Setpoints and presets are zeroed, the soft limits are at the supply's
+    /// maximums, and the output is off.
+    pub fn new(variant: &SupplyVariant) -> Self {
+        let zero = OperatingPoint {
+            voltage: 0.,
+            current: 0.,
+        };
+
+        State {
+            settings: zero,
+            voltage_limit: variant.nominal_max_voltage as f32,
+            current_limit: variant.nominal_max_current as f32,
+            presets: Presets(zero, zero, zero),
+            output: OutputState::Off,
+        }
+    }
+
+    /// Bring every value within what the supply can be set to.
+    ///
+    /// Limits and presets are clamped to the supply's maximums, and the
+    /// setpoints to the limits.
+    fn clamp(&mut self, variant: &SupplyVariant) {
+        let max_voltage = variant.nominal_max_voltage as f32;
+        let max_current = variant.nominal_max_current as f32;
+
+        self.voltage_limit = within(self.voltage_limit, max_voltage);
+        self.current_limit = within(self.current_limit, max_current);
+
+        let settings = &mut self.settings;
+        settings.voltage = within(settings.voltage, self.voltage_limit);
+        settings.current = within(settings.current, self.current_limit);
+
+        let Presets(p0, p1, p2) = &mut self.presets;
+        for preset in [p0, p1, p2] {
+            preset.voltage = within(preset.voltage, max_voltage);
+            preset.current = within(preset.current, max_current);
+        }
+    }
+}
+
+/// Clamp `val` to `0..=max`, treating NaN as zero.
+fn within(val: f32, max: f32) -> f32 {
+    val.max(0.).min(max)
+}
+
+/// An in-process emulation of a 168xB supply.
+///
+/// Commands written to this are executed immediately, and their responses
+/// queued up for reading. It can be used anywhere a real supply's port can:
+///
+/// ```
+/// use bk168xb::{sim::VirtualSupply, Supply, BK1688B};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut supply = Supply::detect(VirtualSupply::new(BK1688B))?;
+/// assert_eq!(supply.variant(), BK1688B);
+///
+/// supply.set_voltage(5.0)?;
+/// assert_eq!(supply.settings()?.voltage, 5.0);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Like a real supply, commands which aren't recognized or whose arguments
+/// are malformed are ignored, and get no response. Reads return end-of-file
+/// whenever no response is waiting, so a missing response is detected
+/// immediately rather than after a timeout.
+///
+/// Setpoints are kept within the "soft" limits: one which is set above its
+/// limit is reduced to it, as is one which a lowered limit no longer allows.
+/// The manual doesn't say how a real supply treats such a setpoint, so code
+/// which depends on it should be checked against hardware.
+///
+/// # Errors
+///
+/// Writing a command fails with [`InvalidData`](io::ErrorKind::InvalidData)
+/// if its response can't be represented. That's only possible if
+/// [`state_mut()`](VirtualSupply::state_mut) has put the supply somewhere a
+/// real one can't go, such as beyond its maximum voltage.
+#[derive(Debug)]
+pub struct VirtualSupply {
+    variant: &'static SupplyVariant,
+    state: State,
+    load: Box<dyn Load>,
+
+    /// Decodes commands as they arrive.
+    rx: Decoder,
+
+    /// Responses waiting to be read.
+    tx: VecDeque<u8>,
+}
+
+impl VirtualSupply {
+    /// Emulate a supply, starting from its default [`State`].
+    pub fn new(variant: &'static SupplyVariant) -> Self {
+        Self::with_state(variant, State::new(variant))
+    }
+
+    /// Emulate a supply, starting from a particular state.
+    ///
+    /// Values which the supply couldn't be set to are clamped to its
+    /// maximums, and setpoints to its soft limits.
+    pub fn with_state(
+        variant: &'static SupplyVariant,
+        mut state: State,
+    ) -> Self {
+        state.clamp(variant);
+
+        VirtualSupply {
+            variant,
+            state,
+            load: Box::new(Open),
+            rx: Decoder::new(),
+            tx: VecDeque::new(),
+        }
+    }
+
+    /// The model of supply being emulated.
+    pub fn variant(&self) -> &'static SupplyVariant {
+        self.variant
+    }
+
+    /// The supply's current state.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Modify the supply's state.
+    ///
+    /// This corresponds to someone twiddling knobs on the front panel.
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// What's connected to the supply's output.
+    pub fn load(&self) -> &dyn Load {
+        &*self.load
+    }
+
+    /// Connect something to the supply's output.
+    ///
+    /// The supply starts out with nothing connected; see [`Open`].
+    pub fn set_load<L: Load + 'static>(&mut self, load: L) {
+        self.load = Box::new(load);
+    }
+
+    /// The supply's absolute maximum output.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_voltage: self.variant.nominal_max_voltage as f32,
+            max_current: self.variant.nominal_max_current as f32,
+        }
+    }
+
+    /// Execute a single command, returning its response.
+    fn execute(&mut self, command: AnyCommand) -> AnyResponse {
+        let state = &mut self.state;
+
+        match command {
+            AnyCommand::SetVoltage(SetVoltage(voltage)) => {
+                state.settings.voltage = voltage;
+            }
+            AnyCommand::SetCurrent(SetCurrent(current)) => {
+                state.settings.current = current;
+            }
+            AnyCommand::SetVoltageLimit(SetVoltageLimit(voltage)) => {
+                state.voltage_limit = voltage;
+            }
+            AnyCommand::SetCurrentLimit(SetCurrentLimit(current)) => {
+                state.current_limit = current;
+            }
+            AnyCommand::SetOutput(SetOutput(output)) => {
+                state.output = output;
+            }
+            AnyCommand::SelectPreset(SelectPreset(preset)) => {
+                state.settings = state.presets[preset];
+            }
+            AnyCommand::SetPresets(SetPresets(p0, p1, p2)) => {
+                state.presets = Presets(p0, p1, p2);
+            }
+            AnyCommand::GetSettings(_) => {
+                let settings = state.settings;
+                return AnyResponse::Settings(Settings {
+                    voltage: settings.voltage,
+                    current: settings.current,
+                });
+            }
+            AnyCommand::GetStatus(_) => {
+                return AnyResponse::Status(self.status());
+            }
+            AnyCommand::GetVoltageLimit(_) => {
+                return AnyResponse::Voltage(Voltage(state.voltage_limit));
+            }
+            AnyCommand::GetCurrentLimit(_) => {
+                return AnyResponse::Current(Current(state.current_limit));
+            }
+            AnyCommand::GetCapabilities(_) => {
+                return AnyResponse::Capabilities(self.capabilities());
+            }
+            AnyCommand::GetPresets(_) => {
+                return AnyResponse::Presets(state.presets);
+            }
+        }
+
+        state.clamp(self.variant);
+        AnyResponse::Ack(())
+    }
+
+    /// Measured output voltage, current, and regulation mode.
+    ///
+    /// The supply holds its voltage setpoint unless the load would draw more
+    /// than the current setpoint. In that case, it drops into constant-current
+    /// mode: the output sits at whatever voltage makes the load draw exactly
+    /// the current setpoint.
+    fn status(&self) -> Status {
+        let settings = self.state.settings;
+        if self.state.output == OutputState::Off {
+            return Status {
+                voltage: 0.,
+                current: 0.,
+                mode: OutputMode::ConstantVoltage,
+            };
+        }
+
+        let current = self.load.current(settings.voltage);
+        if current <= settings.current {
+            return Status {
+                voltage: settings.voltage,
+                current,
+                mode: OutputMode::ConstantVoltage,
+            };
+        }
+
+        // The load's current is monotonic in voltage, so bisect for the
+        // voltage at which it meets the current setpoint.
+        let (mut low, mut high) = (0., settings.voltage);
+        for _ in 0..32 {
+            let mid = (low + high) / 2.;
+            if self.load.current(mid) > settings.current {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        Status {
+            voltage: low,
+            current: settings.current,
+            mode: OutputMode::ConstantCurrent,
+        }
+    }
+}
+
+impl Read for VirtualSupply {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.tx.read(buf)
+    }
+}
+
+impl Write for VirtualSupply {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut input = buf;
+        while !input.is_empty() {
+            // Like a real supply, ignore anything that can't be decoded
+            let command = match self.rx.feed(&mut input, self.variant) {
+                Ok(Some(command)) => command,
+                Ok(None) | Err(_) => continue,
+            };
+
+            let mut response = Vec::new();
+            self.execute(command)
+                .serialize(&mut response, self.variant)
+                .map_err(|err| {
+                    io::Error::new(io::ErrorKind::InvalidData, err)
+                })?;
+            self.tx.extend(response);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        sim::{ConstantCurrent, Diode, Resistor, Short},
+        BK1685B, BK1687B, BK1688B, PresetIndex,
+        response::{Error::NoResponse, Settings, Status, Voltage, Current},
+        supply::{Error, Supply},
+        test_util::{any_psu, high_voltage_psu, low_voltage_psu},
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test is_detected(any_psu) {
+        let variant = any_psu.val;
+
+        let supply = Supply::detect(VirtualSupply::new(variant)).unwrap();
+
+        expect_that!(&supply.variant(), eq(variant));
+    }
+
+    test round_trips_settings(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage(12.3).unwrap();
+        supply.set_current(4.5).unwrap();
+
+        expect_that!(&supply.settings().unwrap(), eq(Settings {
+            voltage: 12.3,
+            current: 4.5,
+        }));
+    }
+
+    test round_trips_high_voltage_settings(high_voltage_psu) {
+        let variant = high_voltage_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage(42.1).unwrap();
+        supply.set_current(1.23).unwrap();
+
+        expect_that!(&supply.settings().unwrap(), eq(Settings {
+            voltage: 42.1,
+            current: 1.23,
+        }));
+    }
+
+    test clamps_to_capabilities(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage(99.9).unwrap();
+
+        let settings = supply.settings().unwrap();
+        expect_that!(&settings.voltage, eq(variant.nominal_max_voltage as f32));
+    }
+
+    test clamps_to_soft_limits(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage_limit(5.).unwrap();
+        supply.set_current_limit(1.).unwrap();
+        supply.set_voltage(10.).unwrap();
+        supply.set_current(2.).unwrap();
+
+        expect_that!(&supply.settings().unwrap(), eq(Settings {
+            voltage: 5.,
+            current: 1.,
+        }));
+    }
+
+    test lowered_limit_reduces_setpoint(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage(10.).unwrap();
+        supply.set_voltage_limit(5.).unwrap();
+
+        expect_that!(&supply.settings().unwrap().voltage, eq(5.));
+    }
+
+    test clamps_initial_state() {
+        let mut state = State::new(BK1685B);
+        state.settings.voltage = 100.;
+        state.voltage_limit = 100.;
+        state.presets.0.current = 50.;
+        let mut supply = Supply::new(
+            VirtualSupply::with_state(BK1685B, state),
+            BK1685B,
+        );
+
+        expect_that!(&supply.settings().unwrap().voltage, eq(60.));
+        expect_that!(&supply.voltage_limit().unwrap(), eq(Voltage(60.)));
+        expect_that!(&supply.presets().unwrap().0.current, eq(5.));
+    }
+
+    test reports_unrepresentable_response(any_psu) {
+        let mut sim = VirtualSupply::new(any_psu.val);
+        sim.state_mut().settings.voltage = 1000.;
+
+        let err = sim.write_all(b"GETS\r").unwrap_err();
+
+        expect_that!(&err.kind(), eq(io::ErrorKind::InvalidData));
+    }
+
+    test round_trips_limits(low_voltage_psu) {
+        let variant = low_voltage_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        supply.set_voltage_limit(15.5).unwrap();
+        supply.set_current_limit(2.5).unwrap();
+
+        expect_that!(&supply.voltage_limit().unwrap(), eq(Voltage(15.5)));
+        expect_that!(&supply.current_limit().unwrap(), eq(Current(2.5)));
+    }
+
+    test round_trips_presets(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+        let presets = Presets(
+            OperatingPoint { voltage: 1.5, current: 0.5 },
+            OperatingPoint { voltage: 3.3, current: 1.0 },
+            OperatingPoint { voltage: 5.0, current: 2.0 },
+        );
+
+        supply.set_presets(presets).unwrap();
+        expect_that!(&supply.presets().unwrap(), eq(presets));
+
+        supply.select_preset(PresetIndex::Two).unwrap();
+        expect_that!(&supply.settings().unwrap(), eq(Settings {
+            voltage: 3.3,
+            current: 1.0,
+        }));
+    }
+
+    test reports_output(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+        supply.set_voltage(5.).unwrap();
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 0.,
+            current: 0.,
+            mode: OutputMode::ConstantVoltage,
+        }));
+
+        supply.set_output(OutputState::On).unwrap();
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 5.,
+            current: 0.,
+            mode: OutputMode::ConstantVoltage,
+        }));
+        expect_that!(&supply.get_ref().state().output, eq(OutputState::On));
+    }
+
+    test starts_from_state(any_psu) {
+        let variant = any_psu.val;
+        let mut state = State::new(variant);
+        state.settings = OperatingPoint { voltage: 2.5, current: 1.0 };
+        let mut supply = Supply::new(
+            VirtualSupply::with_state(variant, state),
+            variant,
+        );
+
+        expect_that!(&supply.settings().unwrap(), eq(Settings {
+            voltage: 2.5,
+            current: 1.0,
+        }));
+    }
+
+    test ignores_invalid_commands(any_psu) {
+        let mut sim = VirtualSupply::new(any_psu.val);
+
+        sim.write_all(b"VOLT\rVOLTxyz\rBOGUS\rGETS1\rRUNM5\rPROM123\r").unwrap();
+
+        let mut reply = Vec::new();
+        sim.read_to_end(&mut reply).unwrap();
+        expect_that!(&reply.is_empty(), eq(true));
+        expect_that!(sim.state(), eq(State::new(any_psu.val)));
+    }
+
+    test missing_response_is_detected(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+
+        // Garble the next command
+        supply.get_mut().write_all(b"VOLT").unwrap();
+
+        let err = supply.status().unwrap_err();
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(NoResponse)
+        }));
+    }
+
+    // A supply with its output on, set to 10V and 2A
+    fixture powered(variant: &'static SupplyVariant) -> Supply<VirtualSupply> {
+        params {
+            vec![BK1685B, BK1687B, BK1688B].into_iter()
+        }
+        setup(&mut self) {
+            let variant = *self.variant;
+            let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+            supply.set_voltage(10.).unwrap();
+            supply.set_current(2.).unwrap();
+            supply.set_output(OutputState::On).unwrap();
+
+            supply
+        }
+    }
+
+    test light_load_is_constant_voltage(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(Resistor(10.));
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 10.,
+            current: 1.,
+            mode: OutputMode::ConstantVoltage,
+        }));
+    }
+
+    test heavy_load_is_constant_current(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(Resistor(2.));
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 4.,
+            current: 2.,
+            mode: OutputMode::ConstantCurrent,
+        }));
+    }
+
+    test short_collapses_voltage(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(Short);
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 0.,
+            current: 2.,
+            mode: OutputMode::ConstantCurrent,
+        }));
+    }
+
+    test current_sink_within_limit(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(ConstantCurrent(1.5));
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 10.,
+            current: 1.5,
+            mode: OutputMode::ConstantVoltage,
+        }));
+    }
+
+    test current_sink_beyond_limit(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(ConstantCurrent(2.5));
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 0.,
+            current: 2.,
+            mode: OutputMode::ConstantCurrent,
+        }));
+    }
+
+    test diode_clamps_voltage(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(Diode::silicon());
+
+        let status = supply.status().unwrap();
+        expect_that!(&status.mode, eq(OutputMode::ConstantCurrent));
+        expect_that!(&status.current, eq(2.));
+        expect_that!(&status.voltage, geq(0.8));
+        expect_that!(&status.voltage, leq(1.2));
+    }
+
+    test unpowered_load_draws_nothing(powered) {
+        let mut supply = powered.val;
+        supply.get_mut().set_load(Short);
+        supply.set_output(OutputState::Off).unwrap();
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 0.,
+            current: 0.,
+            mode: OutputMode::ConstantVoltage,
+        }));
+    }
+}
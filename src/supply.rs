@@ -0,0 +1,14 @@
+//! High-level supply control
+
+#[cfg(feature = "tokio")]
+mod async_supply;
+mod core;
+mod error;
+mod retry;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
+#[cfg(feature = "tokio")]
+pub use self::async_supply::*;
+pub use self::{core::*, error::*, retry::*};
@@ -0,0 +1,915 @@
+use crate::{
+    command::{
+        AnyCommand, Command, CommandSink, GetCapabilities, GetCurrentLimit,
+        GetPresets, GetSettings, GetStatus, GetVoltageLimit, ReadBack,
+        SelectPreset, SetCurrent, SetCurrentLimit, SetOutput, SetPresets,
+        SetVoltage, SetVoltageLimit,
+    },
+    error::Failure,
+    response::{
+        self, frame_end, parse_frame, AnyResponse, Capabilities, Current,
+        Presets, Settings, Status, Voltage,
+    },
+    supply::{check_recalled, Error, Result, RetryPolicy},
+    OutputState, PresetIndex, SupplyVariant,
+};
+
+use std::time::Duration;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::{timeout_at, Instant},
+};
+
+/// An asynchronous connection to a single power supply.
+///
+/// This is the [`tokio`] counterpart of [`Supply`](crate::Supply). Failed
+/// transactions are retried in the same way; see
+/// [`set_retry_policy()`](AsyncSupply::set_retry_policy).
+///
+/// # Timeouts
+///
+/// Each transaction must complete within the configured
+/// [`timeout()`](AsyncSupply::timeout). If nothing arrives in that time, it
+/// fails with [`NoResponse`](crate::response::Error::NoResponse); if only part
+/// of a response arrives, it fails with
+/// [`MalformedResponse`](crate::response::Error::MalformedResponse).
+///
+/// A response which arrives after its transaction has timed out is discarded.
+/// Until it does, later transactions wait for it before sending their own
+/// command, within their own timeout. One which runs out of time while
+/// waiting fails with a [`WriteFailure`](crate::command::Error::WriteFailure),
+/// having sent nothing. If a response is lost altogether,
+/// [`resync()`](AsyncSupply::resync) brings the link back into step; a
+/// [retry policy](AsyncSupply::set_retry_policy) does so before each retry.
+///
+/// # Cancellation safety
+///
+/// All transaction methods are cancellation safe: if a transaction's future is
+/// dropped before completing, the connection is left in a consistent state.
+/// Specifically:
+///
+/// - A command which was partially written is completed before the next one is
+///   sent, so the supply never sees a corrupted command
+/// - A command which wasn't written at all is never sent
+/// - The response to an abandoned command is discarded when it arrives, rather
+///   than being mistaken for the response to the next command
+#[derive(Debug)]
+pub struct AsyncSupply<T> {
+    port: T,
+    variant: &'static SupplyVariant,
+    policy: RetryPolicy,
+
+    /// Received bytes which haven't yet been parsed.
+    rx: Vec<u8>,
+
+    /// What the current transaction has consumed from `rx`, for error
+    /// reporting.
+    last_rx: Vec<u8>,
+
+    /// The command currently being sent, and how much of it has been written.
+    tx: Vec<u8>,
+    tx_written: usize,
+
+    /// Commands which have been sent, but whose response hasn't been read.
+    unanswered: usize,
+}
+
+impl<T> AsyncSupply<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Create a new supply connection.
+    ///
+    /// # Arguments
+    ///
+    /// - `port`: Link to the supply
+    /// - `variant`: Which model of supply is on the other end of `port`
+    pub fn new(port: T, variant: &'static SupplyVariant) -> Self {
+        AsyncSupply {
+            port,
+            variant,
+            policy: RetryPolicy::default(),
+            rx: Vec::new(),
+            last_rx: Vec::new(),
+            tx: Vec::new(),
+            tx_written: 0,
+            unanswered: 0,
+        }
+    }
+
+    /// The model of supply this connection is talking to.
+    pub fn variant(&self) -> &'static SupplyVariant {
+        self.variant
+    }
+
+    /// How long each transaction may take.
+    pub fn timeout(&self) -> Duration {
+        self.policy.timeout
+    }
+
+    /// Change how long each transaction may take.
+    ///
+    /// This is a shorthand for changing the retry policy's
+    /// [`timeout`](RetryPolicy::timeout).
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.policy.timeout = timeout;
+    }
+
+    /// How failed transactions are retried.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Change how failed transactions are retried.
+    ///
+    /// See [`Supply::set_retry_policy()`](crate::Supply::set_retry_policy).
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.policy = policy;
+    }
+
+    /// Get a reference to the underlying port.
+    pub fn get_ref(&self) -> &T {
+        &self.port
+    }
+
+    /// Get a mutable reference to the underlying port.
+    ///
+    /// Reading from or writing to the port directly may leave the supply in
+    /// the middle of a command/response exchange.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.port
+    }
+
+    /// Close the connection, returning the underlying port.
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Get the supply's instantaneous output state.
+    pub async fn status(&mut self) -> Result<Status> {
+        self.transact(&GetStatus).await
+    }
+
+    /// Get the supply's configured voltage and current.
+    pub async fn settings(&mut self) -> Result<Settings> {
+        self.transact(&GetSettings).await
+    }
+
+    /// Get the "soft" voltage limit.
+    pub async fn voltage_limit(&mut self) -> Result<Voltage> {
+        self.transact(&GetVoltageLimit).await
+    }
+
+    /// Get the "soft" current limit.
+    pub async fn current_limit(&mut self) -> Result<Current> {
+        self.transact(&GetCurrentLimit).await
+    }
+
+    /// Get the supply's absolute maximum voltage and current.
+    pub async fn capabilities(&mut self) -> Result<Capabilities> {
+        self.transact(&GetCapabilities).await
+    }
+
+    /// Get the supply's pre-set operating points.
+    pub async fn presets(&mut self) -> Result<Presets> {
+        self.transact(&GetPresets).await
+    }
+
+    /// Set the output voltage.
+    pub async fn set_voltage<V: Into<SetVoltage>>(
+        &mut self,
+        voltage: V,
+    ) -> Result<()> {
+        self.transact_verified(&voltage.into()).await
+    }
+
+    /// Set the output current.
+    pub async fn set_current<I: Into<SetCurrent>>(
+        &mut self,
+        current: I,
+    ) -> Result<()> {
+        self.transact_verified(&current.into()).await
+    }
+
+    /// Set the "soft" voltage limit.
+    pub async fn set_voltage_limit<V: Into<SetVoltageLimit>>(
+        &mut self,
+        limit: V,
+    ) -> Result<()> {
+        self.transact_verified(&limit.into()).await
+    }
+
+    /// Set the "soft" current limit.
+    pub async fn set_current_limit<I: Into<SetCurrentLimit>>(
+        &mut self,
+        limit: I,
+    ) -> Result<()> {
+        self.transact_verified(&limit.into()).await
+    }
+
+    /// Turn the supply's output on or off.
+    pub async fn set_output(&mut self, state: OutputState) -> Result<()> {
+        self.transact(&SetOutput(state)).await
+    }
+
+    /// Configure the supply's pre-set operating points.
+    pub async fn set_presets<P: Into<SetPresets>>(
+        &mut self,
+        presets: P,
+    ) -> Result<()> {
+        self.transact_verified(&presets.into()).await
+    }
+
+    /// Switch to one of the supply's pre-set operating points.
+    pub async fn select_preset(&mut self, preset: PresetIndex) -> Result<()> {
+        self.transact(&SelectPreset(preset)).await
+    }
+
+    /// Switch to one of the supply's pre-set operating points, and check that
+    /// the supply's settings now match it.
+    ///
+    /// See [`Supply::recall_preset()`](crate::Supply::recall_preset).
+    pub async fn recall_preset(
+        &mut self,
+        preset: PresetIndex,
+    ) -> Result<Settings> {
+        let expected = self.presets().await?[preset];
+        self.select_preset(preset).await?;
+        let actual = self.settings().await?;
+
+        check_recalled(preset, expected, actual, self.variant)
+    }
+
+    /// Send a command, and receive its response.
+    ///
+    /// If the command is [idempotent](Command::IDEMPOTENT), a failed
+    /// transaction is retried according to the
+    /// [retry policy](AsyncSupply::set_retry_policy).
+    pub async fn transact<C: Command>(
+        &mut self,
+        command: &C,
+    ) -> Result<C::Response> {
+        let policy = self.policy;
+        let mut retry = 0;
+
+        loop {
+            match self.transact_once(command).await {
+                Err(err) if C::IDEMPOTENT && policy.may_retry(&err, retry) => {
+                    self.prepare_retry(retry).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a state-changing command, retrying it if necessary.
+    ///
+    /// See [`Supply::transact_verified()`](crate::Supply::transact_verified).
+    pub async fn transact_verified<C: ReadBack>(
+        &mut self,
+        command: &C,
+    ) -> Result<()> {
+        let mut retry = 0;
+
+        loop {
+            let err = match self.transact_once(command).await {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            if !self.policy.may_retry(&err, retry) {
+                return Err(err);
+            }
+            self.prepare_retry(retry).await;
+            retry += 1;
+
+            // If the read-back fails too, the command is simply sent again.
+            if let Ok(state) = self.transact_once(&C::QUERY).await {
+                if command.is_applied(&state, self.variant) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Bring the link back into step with the supply.
+    ///
+    /// This is needed if a response never arrives, as later transactions
+    /// would otherwise keep waiting for it. A [`GetStatus`] probe is sent, and
+    /// everything up to the end of the last response to arrive within the
+    /// timeout is discarded. The supply answers commands in order, so any
+    /// response still outstanding by then is taken to have been lost.
+    ///
+    /// See [`Supply::resync()`](crate::Supply::resync).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Response`](Error::Response) error if nothing at all
+    /// arrives before the timeout.
+    pub async fn resync(&mut self) -> Result<()> {
+        let deadline = Instant::now() + self.policy.timeout;
+
+        self.last_rx.clear();
+        let result = self.probe(deadline).await;
+
+        result.map_err(|err| err.into_error(GetStatus::FUNCTION, &self.last_rx))
+    }
+
+    /// Send a command chosen at runtime, and receive its response.
+    ///
+    /// See [`Supply::transact_any()`](crate::Supply::transact_any).
+    pub async fn transact_any(
+        &mut self,
+        command: &AnyCommand,
+    ) -> Result<AnyResponse> {
+        match command {
+            AnyCommand::GetSettings(c) => self.transact_as(c).await,
+            AnyCommand::GetStatus(c) => self.transact_as(c).await,
+            AnyCommand::GetVoltageLimit(c) => self.transact_as(c).await,
+            AnyCommand::GetCurrentLimit(c) => self.transact_as(c).await,
+            AnyCommand::GetCapabilities(c) => self.transact_as(c).await,
+            AnyCommand::GetPresets(c) => self.transact_as(c).await,
+            AnyCommand::SetVoltage(c) => self.transact_as(c).await,
+            AnyCommand::SetCurrent(c) => self.transact_as(c).await,
+            AnyCommand::SetVoltageLimit(c) => self.transact_as(c).await,
+            AnyCommand::SetCurrentLimit(c) => self.transact_as(c).await,
+            AnyCommand::SetOutput(c) => self.transact_as(c).await,
+            AnyCommand::SetPresets(c) => self.transact_as(c).await,
+            AnyCommand::SelectPreset(c) => self.transact_as(c).await,
+        }
+    }
+
+    /// Run the query with the given function, such as `"GETD"`.
+    ///
+    /// See [`Supply::query()`](crate::Supply::query).
+    pub async fn query(&mut self, function: &str) -> Result<AnyResponse> {
+        let command = AnyCommand::from_function(function, self.variant)
+            .map_err(|source| Error::Command {
+                function: function.to_owned(),
+                source,
+            })?;
+
+        self.transact_any(&command).await
+    }
+
+    async fn transact_as<C>(&mut self, command: &C) -> Result<AnyResponse>
+    where
+        C: Command,
+        C::Response: Into<AnyResponse>,
+    {
+        self.transact(command).await.map(Into::into)
+    }
+
+    async fn transact_once<C: Command>(
+        &mut self,
+        command: &C,
+    ) -> Result<C::Response> {
+        // Serialize up-front, so that an unrepresentable command doesn't
+        // disturb the link at all.
+        let mut raw = Vec::new();
+        raw.send_command(command, self.variant)
+            .map_err(|err| Failure::from(err).into_error(C::FUNCTION, &[]))?;
+
+        self.last_rx.clear();
+        let result = self.timed_exchange(raw).await;
+
+        result.map_err(|err| err.into_error(C::FUNCTION, &self.last_rx))
+    }
+
+    /// Exchange a command for its response, within the timeout.
+    async fn timed_exchange<R: response::Response>(
+        &mut self,
+        raw: Vec<u8>,
+    ) -> std::result::Result<R, Failure> {
+        let deadline = Instant::now() + self.policy.timeout;
+
+        if timeout_at(deadline, self.settle()).await.is_err() {
+            // Earlier commands are still being written or answered, so this
+            // one can't be sent yet.
+            return Err(timed_out_writing());
+        }
+
+        match timeout_at(deadline, self.exchange(raw)).await {
+            Ok(result) => result,
+            Err(_) => Err(self.timed_out().into()),
+        }
+    }
+
+    /// Finish any partially-written command, and discard the responses to
+    /// earlier commands which haven't been read.
+    async fn settle(&mut self) -> std::result::Result<(), Failure> {
+        self.finish_write().await?;
+
+        while self.unanswered != 0 {
+            self.read_frame().await?;
+            self.unanswered -= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Send a probe, and discard everything up to the last response to
+    /// arrive before `deadline`.
+    async fn probe(
+        &mut self,
+        deadline: Instant,
+    ) -> std::result::Result<(), Failure> {
+        let mut raw = Vec::new();
+        raw.send_command(&GetStatus, self.variant)?;
+
+        let send = async {
+            self.finish_write().await?;
+            self.tx = raw;
+            self.write_pending().await
+        };
+        match timeout_at(deadline, send).await {
+            Ok(result) => result?,
+            Err(_) => return Err(timed_out_writing()),
+        }
+
+        let mut answered = false;
+        while self.unanswered != 0 {
+            match timeout_at(deadline, self.read_frame()).await {
+                Ok(frame) => {
+                    frame?;
+                    self.unanswered -= 1;
+                    answered = true;
+                }
+                Err(_) if answered && self.rx.is_empty() => {
+                    // The last response was the probe's, and the ones before
+                    // it were lost.
+                    self.unanswered = 0;
+                }
+                Err(_) => return Err(self.timed_out().into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Back off, and resync if responses are still outstanding.
+    async fn prepare_retry(&mut self, retry: u32) {
+        tokio::time::sleep(self.policy.delay(retry)).await;
+
+        if self.unanswered != 0 {
+            // Any error will recur on the retry itself.
+            let _ = self.resync().await;
+        }
+    }
+
+    async fn exchange<R: response::Response>(
+        &mut self,
+        raw: Vec<u8>,
+    ) -> std::result::Result<R, Failure> {
+        self.tx = raw;
+        self.write_pending().await?;
+
+        let frame = self.read_frame().await?;
+        self.unanswered -= 1;
+
+        Ok(parse_frame(&frame, self.variant)?)
+    }
+
+    /// Account for a response which didn't arrive in time.
+    ///
+    /// The response is still counted as unanswered, so that if it does turn
+    /// up, it's discarded rather than mistaken for the response to the next
+    /// command. Any part of it which has already arrived is kept for the same
+    /// reason.
+    fn timed_out(&mut self) -> response::Error {
+        if self.rx.is_empty() {
+            response::Error::NoResponse
+        } else {
+            self.last_rx = self.rx.clone();
+            response::Error::MalformedResponse
+        }
+    }
+
+    /// Finish writing a partially-written command, and forget one which was
+    /// abandoned before any of it was sent.
+    async fn finish_write(&mut self) -> std::result::Result<(), Failure> {
+        if self.tx_written == 0 {
+            self.tx.clear();
+        }
+
+        self.write_pending().await
+    }
+
+    /// Finish writing the current command, if any.
+    async fn write_pending(&mut self) -> std::result::Result<(), Failure> {
+        if self.tx.is_empty() {
+            return Ok(());
+        }
+
+        while self.tx_written < self.tx.len() {
+            let written = self
+                .port
+                .write(&self.tx[self.tx_written..])
+                .await
+                .map_err(crate::command::Error::from)?;
+            if written == 0 {
+                let err = std::io::ErrorKind::WriteZero.into();
+                return Err(crate::command::Error::WriteFailure(err).into());
+            }
+
+            self.tx_written += written;
+        }
+        self.port
+            .flush()
+            .await
+            .map_err(crate::command::Error::from)?;
+
+        self.tx.clear();
+        self.tx_written = 0;
+        self.unanswered += 1;
+
+        Ok(())
+    }
+
+    /// Read a single raw response, including its terminator.
+    async fn read_frame(&mut self) -> std::result::Result<Vec<u8>, Failure> {
+        loop {
+            if let Some(end) = frame_end(&self.rx) {
+                self.last_rx = self.rx.drain(..end).collect();
+
+                return Ok(self.last_rx.clone());
+            }
+
+            let mut chunk = [0; 64];
+            let count = self
+                .port
+                .read(&mut chunk)
+                .await
+                .map_err(response::Error::from)?;
+            if count == 0 {
+                let err = if self.rx.is_empty() {
+                    response::Error::NoResponse
+                } else {
+                    self.last_rx = std::mem::take(&mut self.rx);
+                    response::Error::MalformedResponse
+                };
+
+                return Err(err.into());
+            }
+
+            self.rx.extend_from_slice(&chunk[..count]);
+        }
+    }
+}
+
+/// The failure for a command which couldn't be sent in time.
+fn timed_out_writing() -> Failure {
+    let err = std::io::ErrorKind::TimedOut.into();
+
+    crate::command::Error::WriteFailure(err).into()
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        response::Error::{MalformedResponse, NoResponse},
+        supply::Error,
+        test_util::{any_psu, low_voltage_psu},
+        OutputMode,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+    use std::future::Future;
+    use tokio::io::{duplex, DuplexStream};
+
+    /// Run a test body to completion.
+    fn run<F: Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    /// Play the part of a supply: wait for each command, then reply.
+    ///
+    /// Replies are written a few bytes at a time, like a slow serial port.
+    async fn respond(
+        mut port: DuplexStream,
+        script: Vec<(&'static str, &'static str)>,
+    ) -> DuplexStream {
+        for (command, reply) in script {
+            let mut received = vec![0; command.len()];
+            port.read_exact(&mut received).await.unwrap();
+            assert_eq!(std::str::from_utf8(&received).unwrap(), command);
+
+            for chunk in reply.as_bytes().chunks(4) {
+                port.write_all(chunk).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        }
+
+        port
+    }
+
+    fn retrying(
+        port: DuplexStream,
+        variant: &'static SupplyVariant,
+    ) -> AsyncSupply<DuplexStream> {
+        let mut supply = AsyncSupply::new(port, variant);
+        supply.set_retry_policy(RetryPolicy {
+            timeout: Duration::from_millis(20),
+            max_retries: 1,
+            backoff: Duration::ZERO,
+        });
+
+        supply
+    }
+
+    test transacts(low_voltage_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, low_voltage_psu.val);
+
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETD\r", "030201451\rOK\r"),
+                ("GETS\r", "123456\rOK\r"),
+                ("VOLT050\r", "OK\r"),
+            ]));
+
+            let status = supply.status().await.unwrap();
+            expect_that!(&status, eq(Status {
+                voltage: 3.02,
+                current: 1.45,
+                mode: OutputMode::ConstantCurrent,
+            }));
+
+            let settings = supply.settings().await.unwrap();
+            expect_that!(&settings, eq(Settings {
+                voltage: 12.3,
+                current: 45.6,
+            }));
+
+            supply.set_voltage(5.).await.unwrap();
+
+            remote.await.unwrap();
+        });
+    }
+
+    test transacts_dynamically(low_voltage_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, low_voltage_psu.val);
+
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GOVP\r", "123\rOK\r"),
+                ("SOUT0\r", "OK\r"),
+            ]));
+
+            let limit = supply.query("GOVP").await.unwrap();
+            expect_that!(&limit, eq(AnyResponse::Voltage(Voltage(12.3))));
+
+            let command = AnyCommand::SetOutput(SetOutput(OutputState::On));
+            let ack = supply.transact_any(&command).await.unwrap();
+            expect_that!(&ack, eq(AnyResponse::Ack(())));
+
+            remote.await.unwrap();
+        });
+    }
+
+    test times_out(any_psu) {
+        run(async {
+            let (port, _remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, any_psu.val);
+            supply.set_timeout(Duration::from_millis(10));
+
+            let err = supply.status().await.unwrap_err();
+
+            assert_that!(&err, has_structure!(Error::Response {
+                source: is_variant!(NoResponse)
+            }));
+        });
+    }
+
+    test reports_partial_response(any_psu) {
+        run(async {
+            let (port, mut remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, any_psu.val);
+            supply.set_timeout(Duration::from_millis(10));
+            remote.write_all(b"0302").await.unwrap();
+
+            let err = supply.status().await.unwrap_err();
+
+            assert_that!(&err, has_structure!(Error::Response {
+                source: is_variant!(MalformedResponse)
+            }));
+            expect_that!(&err.received(), eq(Some(&b"0302"[..])));
+        });
+    }
+
+    test discards_late_response(low_voltage_psu) {
+        run(async {
+            let (port, mut remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, low_voltage_psu.val);
+            supply.set_timeout(Duration::from_millis(20));
+
+            let mut received = [0; 5];
+            let (status, _) = tokio::join!(
+                supply.status(),
+                remote.read_exact(&mut received),
+            );
+            status.unwrap_err();
+
+            // The reply to GETD arrives only after it has timed out
+            remote.write_all(b"0302").await.unwrap();
+            let remote = tokio::spawn(async move {
+                remote.write_all(b"01451\rOK\r").await.unwrap();
+                respond(remote, vec![("GETS\r", "123456\rOK\r")]).await
+            });
+
+            let settings = supply.settings().await.unwrap();
+            expect_that!(&settings, eq(Settings {
+                voltage: 12.3,
+                current: 45.6,
+            }));
+
+            remote.await.unwrap();
+        });
+    }
+
+    test discards_response_later_than_next_timeout(low_voltage_psu) {
+        run(async {
+            let (port, mut remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, low_voltage_psu.val);
+            supply.set_timeout(Duration::from_millis(10));
+
+            let mut received = [0; 5];
+            let (status, _) = tokio::join!(
+                supply.status(),
+                remote.read_exact(&mut received),
+            );
+            status.unwrap_err();
+
+            // The reply to GETD is still outstanding, so GETS isn't sent
+            let err = supply.settings().await.unwrap_err();
+            assert_that!(&err, has_structure!(Error::Command {
+                source: is_variant!(crate::command::Error::WriteFailure)
+            }));
+
+            // When the reply does arrive, it isn't mistaken for GETS's
+            remote.write_all(b"030201451\rOK\r").await.unwrap();
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETS\r", "123456\rOK\r"),
+            ]));
+
+            let settings = supply.settings().await.unwrap();
+            expect_that!(&settings, eq(Settings {
+                voltage: 12.3,
+                current: 45.6,
+            }));
+
+            remote.await.unwrap();
+        });
+    }
+
+    test recovers_from_lost_response(low_voltage_psu) {
+        run(async {
+            let (port, mut remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, low_voltage_psu.val);
+            supply.set_timeout(Duration::from_millis(10));
+
+            let mut received = [0; 5];
+            let (status, _) = tokio::join!(
+                supply.status(),
+                remote.read_exact(&mut received),
+            );
+            status.unwrap_err();
+
+            // GETD is never answered, so GETS can't be sent
+            let err = supply.settings().await.unwrap_err();
+            assert_that!(&err, has_structure!(Error::Command {
+                source: is_variant!(crate::command::Error::WriteFailure)
+            }));
+
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETD\r", "000000000\rOK\r"),
+                ("GETS\r", "123456\rOK\r"),
+            ]));
+
+            supply.resync().await.unwrap();
+            let settings = supply.settings().await.unwrap();
+            expect_that!(&settings, eq(Settings {
+                voltage: 12.3,
+                current: 45.6,
+            }));
+
+            remote.await.unwrap();
+        });
+    }
+
+    test retries_queries(any_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = retrying(port, any_psu.val);
+
+            // The first GETD's response is lost, so the link is resynced
+            // before it's sent again
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETD\r", ""),
+                ("GETD\r", "030201451\rOK\r"),
+                ("GETD\r", "030201451\rOK\r"),
+            ]));
+
+            supply.status().await.unwrap();
+
+            remote.await.unwrap();
+        });
+    }
+
+    test reads_back_before_retrying(low_voltage_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = retrying(port, low_voltage_psu.val);
+
+            // The voltage was set, so it isn't sent again
+            let remote = tokio::spawn(respond(remote, vec![
+                ("VOLT050\r", ""),
+                ("GETD\r", "000000000\rOK\r"),
+                ("GETS\r", "050010\rOK\r"),
+            ]));
+
+            supply.set_voltage(5.).await.unwrap();
+
+            remote.await.unwrap();
+        });
+    }
+
+    test reports_closed_port(any_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, any_psu.val);
+
+            let remote = tokio::spawn(async move {
+                let mut remote = remote;
+                let mut received = [0; 5];
+                remote.read_exact(&mut received).await.unwrap();
+                remote.write_all(b"0302").await.unwrap();
+            });
+
+            let err = supply.status().await.unwrap_err();
+            remote.await.unwrap();
+
+            assert_that!(&err, has_structure!(Error::Response {
+                source: is_variant!(MalformedResponse)
+            }));
+        });
+    }
+
+    test discards_response_to_cancelled_command(any_psu) {
+        run(async {
+            let (port, remote) = duplex(64);
+            let mut supply = AsyncSupply::new(port, any_psu.val);
+
+            // Abandon the status request once it has been sent
+            tokio::select! {
+                biased;
+                _ = supply.status() => panic!("status shouldn't complete"),
+                _ = tokio::task::yield_now() => {},
+            }
+
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETD\r", "000000000\rOK\r"),
+                ("SOUT0\r", "OK\r"),
+            ]));
+
+            supply.set_output(OutputState::On).await.unwrap();
+
+            remote.await.unwrap();
+        });
+    }
+
+    test completes_partially_written_command(any_psu) {
+        run(async {
+            // Small enough that a command can't be written all at once
+            let (port, remote) = duplex(2);
+            let mut supply = AsyncSupply::new(port, any_psu.val);
+
+            tokio::select! {
+                biased;
+                _ = supply.status() => panic!("status shouldn't complete"),
+                _ = tokio::task::yield_now() => {},
+            }
+
+            let remote = tokio::spawn(respond(remote, vec![
+                ("GETD\r", "000000000\rOK\r"),
+                ("SOUT1\r", "OK\r"),
+            ]));
+
+            supply.set_output(OutputState::Off).await.unwrap();
+
+            remote.await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,929 @@
+use crate::{
+    command::{
+        AnyCommand, Command, CommandSink, GetCapabilities, GetCurrentLimit,
+        GetPresets, GetSettings, GetStatus, GetVoltageLimit, ReadBack,
+        SelectPreset, SetCurrent, SetCurrentLimit, SetOutput, SetPresets,
+        SetVoltage, SetVoltageLimit,
+    },
+    error::Failure,
+    response::{
+        self, AnyResponse, Capabilities, Current, Presets, ResponseReader,
+        ResponseSource, Settings, Status, Voltage,
+    },
+    supply::{Error, Result, RetryPolicy},
+    OperatingPoint, OutputState, PresetIndex, SupplyVariant, BK1685B,
+};
+
+use std::{io, thread, time::Duration};
+
+/// A connection to a single power supply.
+///
+/// This pairs each [`Command`](crate::command::Command) with the
+/// [`Response`](crate::response::Response) the supply sends back, so callers
+/// never have to know which reply format follows which command.
+///
+/// The underlying `port` is usually a serial port, but can be anything which
+/// is both readable and writable. Responses are read through a
+/// [`ResponseReader`], so they need not arrive in a single read.
+///
+/// Timeouts are only enforced between reads, so a read from `port` must not
+/// block indefinitely: a serial port needs its own read timeout, or a lost
+/// response hangs the connection. With the `serialport` feature,
+/// [`SerialTransport`](crate::transport::SerialTransport) opens a port
+/// configured for the supply, read timeout included.
+///
+/// By default, failed transactions aren't retried; see
+/// [`set_retry_policy()`](Supply::set_retry_policy).
+#[derive(Debug)]
+pub struct Supply<T> {
+    port: ResponseReader<T>,
+    variant: &'static SupplyVariant,
+    policy: RetryPolicy,
+}
+
+impl<T> Supply<T>
+where
+    T: io::Read + io::Write,
+{
+    /// Create a new supply connection.
+    ///
+    /// # Arguments
+    ///
+    /// - `port`: Link to the supply
+    /// - `variant`: Which model of supply is on the other end of `port`
+    pub fn new(port: T, variant: &'static SupplyVariant) -> Self {
+        let policy = RetryPolicy::default();
+
+        Supply {
+            port: ResponseReader::with_timeout(port, policy.timeout),
+            variant,
+            policy,
+        }
+    }
+
+    /// Connect to a supply of unknown model.
+    ///
+    /// The supply is asked for its [`Capabilities`], which are used to
+    /// determine which model it is. If `port` must outlive a failed
+    /// detection, pass it by mutable reference.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the usual communication failures, this returns
+    /// [`Error::UnknownVariant`](crate::supply::Error::UnknownVariant) if the
+    /// reported capabilities don't match any supported supply.
+    pub fn detect(port: T) -> Result<Self> {
+        // The capabilities command and response are the same for all models,
+        // so the initial choice of variant is arbitrary.
+        let mut supply = Supply::new(port, BK1685B);
+        let caps = supply.capabilities()?;
+        supply.variant = caps.variant().ok_or(Error::UnknownVariant(caps))?;
+
+        Ok(supply)
+    }
+
+    /// The model of supply this connection is talking to.
+    pub fn variant(&self) -> &'static SupplyVariant {
+        self.variant
+    }
+
+    /// How long to wait for each response.
+    pub fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    /// Change how long to wait for each response.
+    ///
+    /// This is a shorthand for changing the retry policy's
+    /// [`timeout`](RetryPolicy::timeout).
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.policy.timeout = timeout;
+        self.port.set_timeout(timeout);
+    }
+
+    /// How failed transactions are retried.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Change how failed transactions are retried.
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "serialport")]
+    /// use bk168xb::{
+    ///     supply::RetryPolicy, transport::SerialTransport, Supply, BK1687B,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(feature = "serialport")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let port = SerialTransport::open("/dev/ttyUSB0")?;
+    /// let mut supply = Supply::new(port, BK1687B);
+    /// supply.set_retry_policy(RetryPolicy {
+    ///     timeout: Duration::from_millis(500),
+    ///     max_retries: 3,
+    ///     backoff: Duration::from_millis(50),
+    /// });
+    ///
+    /// // Sent up to four times, if the supply doesn't answer
+    /// let status = supply.status()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "serialport"))]
+    /// # fn main() {}
+    /// ```
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.port.set_timeout(policy.timeout);
+        self.policy = policy;
+    }
+
+    /// Get a reference to the underlying port.
+    pub fn get_ref(&self) -> &T {
+        self.port.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying port.
+    ///
+    /// Reading from or writing to the port directly may leave the supply in
+    /// the middle of a command/response exchange.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.port.get_mut()
+    }
+
+    /// Close the connection, returning the underlying port.
+    pub fn into_inner(self) -> T {
+        self.port.into_inner()
+    }
+
+    /// Get the supply's instantaneous output state.
+    pub fn status(&mut self) -> Result<Status> {
+        self.transact(&GetStatus)
+    }
+
+    /// Get the supply's configured voltage and current.
+    pub fn settings(&mut self) -> Result<Settings> {
+        self.transact(&GetSettings)
+    }
+
+    /// Get the "soft" voltage limit.
+    pub fn voltage_limit(&mut self) -> Result<Voltage> {
+        self.transact(&GetVoltageLimit)
+    }
+
+    /// Get the "soft" current limit.
+    pub fn current_limit(&mut self) -> Result<Current> {
+        self.transact(&GetCurrentLimit)
+    }
+
+    /// Get the supply's absolute maximum voltage and current.
+    pub fn capabilities(&mut self) -> Result<Capabilities> {
+        self.transact(&GetCapabilities)
+    }
+
+    /// Get the supply's pre-set operating points.
+    pub fn presets(&mut self) -> Result<Presets> {
+        self.transact(&GetPresets)
+    }
+
+    /// Set the output voltage.
+    pub fn set_voltage<V: Into<SetVoltage>>(
+        &mut self,
+        voltage: V,
+    ) -> Result<()> {
+        self.transact_verified(&voltage.into())
+    }
+
+    /// Set the output current.
+    pub fn set_current<I: Into<SetCurrent>>(
+        &mut self,
+        current: I,
+    ) -> Result<()> {
+        self.transact_verified(&current.into())
+    }
+
+    /// Set the "soft" voltage limit.
+    pub fn set_voltage_limit<V: Into<SetVoltageLimit>>(
+        &mut self,
+        limit: V,
+    ) -> Result<()> {
+        self.transact_verified(&limit.into())
+    }
+
+    /// Set the "soft" current limit.
+    pub fn set_current_limit<I: Into<SetCurrentLimit>>(
+        &mut self,
+        limit: I,
+    ) -> Result<()> {
+        self.transact_verified(&limit.into())
+    }
+
+    /// Turn the supply's output on or off.
+    ///
+    /// The supply doesn't report its output state, but setting it again is
+    /// harmless, so this is simply resent if its response is lost.
+    pub fn set_output(&mut self, state: OutputState) -> Result<()> {
+        self.transact(&SetOutput(state))
+    }
+
+    /// Configure the supply's pre-set operating points.
+    pub fn set_presets<P: Into<SetPresets>>(
+        &mut self,
+        presets: P,
+    ) -> Result<()> {
+        self.transact_verified(&presets.into())
+    }
+
+    /// Switch to one of the supply's pre-set operating points.
+    ///
+    /// Which preset is selected can't be read back, so this is never retried.
+    pub fn select_preset(&mut self, preset: PresetIndex) -> Result<()> {
+        self.transact(&SelectPreset(preset))
+    }
+
+    /// Switch to one of the supply's pre-set operating points, and check that
+    /// the supply's settings now match it.
+    ///
+    /// The stored presets are read before the preset is selected, and the
+    /// settings afterwards. They're compared at the precision the supply
+    /// works to.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the usual communication failures, this returns
+    /// [`PresetMismatch`](Error::PresetMismatch) if the settings don't match
+    /// the preset.
+    pub fn recall_preset(&mut self, preset: PresetIndex) -> Result<Settings> {
+        let expected = self.presets()?[preset];
+        self.select_preset(preset)?;
+        let actual = self.settings()?;
+
+        check_recalled(preset, expected, actual, self.variant)
+    }
+
+    /// Send a command, and receive its response.
+    ///
+    /// The type of the response is determined by the command, so this can be
+    /// used for any command -- including those without a dedicated method.
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "serialport")]
+    /// use bk168xb::{
+    ///     command::GetStatus, transport::SerialTransport, Supply, BK1687B,
+    /// };
+    ///
+    /// # #[cfg(feature = "serialport")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let port = SerialTransport::open("/dev/ttyUSB0")?;
+    /// let mut supply = Supply::new(port, BK1687B);
+    ///
+    /// let status = supply.transact(&GetStatus)?;
+    /// println!("{:?}", status.mode);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "serialport"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// # Retries
+    ///
+    /// If the command is [idempotent](Command::IDEMPOTENT), a failed
+    /// transaction is retried according to the
+    /// [retry policy](Supply::set_retry_policy). Other commands are sent
+    /// exactly once; see [`transact_verified()`](Supply::transact_verified).
+    ///
+    /// # Framing errors
+    ///
+    /// If the response is the wrong length, or isn't terminated properly, the
+    /// link is probably out of step: the rest of this response, or the
+    /// remains of an earlier one, may still be on their way. The link is
+    /// [resynchronized](Supply::resync) before the error is returned (or the
+    /// command retried), so that such input isn't mistaken for the response
+    /// to the next command.
+    ///
+    /// Resyncing reads until the port goes quiet, so this only happens once
+    /// the port has [shown](ResponseReader::returns_when_idle) that its reads
+    /// time out. Otherwise it could block forever.
+    pub fn transact<C: Command>(&mut self, command: &C) -> Result<C::Response> {
+        let policy = self.policy;
+        let mut retry = 0;
+
+        loop {
+            match self.transact_once(command) {
+                Err(err) if C::IDEMPOTENT && policy.may_retry(&err, retry) => {
+                    self.prepare_retry(retry);
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a state-changing command, retrying it if necessary.
+    ///
+    /// If the command's acknowledgement is lost, the supply may or may not
+    /// have acted on it. Before the command is sent again, its effect is
+    /// [read back](ReadBack) to find out; if it has already taken effect, this
+    /// returns successfully without resending it.
+    ///
+    /// Retries are made according to the
+    /// [retry policy](Supply::set_retry_policy).
+    pub fn transact_verified<C: ReadBack>(
+        &mut self,
+        command: &C,
+    ) -> Result<()> {
+        let mut retry = 0;
+
+        loop {
+            let err = match self.transact_once(command) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            if !self.policy.may_retry(&err, retry) {
+                return Err(err);
+            }
+            self.prepare_retry(retry);
+            retry += 1;
+
+            // If the read-back fails too, the command is simply sent again.
+            if let Ok(state) = self.transact_once(&C::QUERY) {
+                if command.is_applied(&state, self.variant) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Bring the link back into step with the supply.
+    ///
+    /// Stale input is drained, and the supply is then sent a harmless
+    /// [`GetStatus`] probe. Everything up to the end of the last response to
+    /// arrive is discarded, so the next transaction starts afresh even if
+    /// late responses to earlier commands were still on their way.
+    ///
+    /// This is useful after a timeout, when a late response would otherwise be
+    /// mistaken for the response to the next command.
+    ///
+    /// Stale input is drained until the port goes quiet, so this blocks
+    /// forever if the port's reads never time out.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Response`](Error::Response) error if the supply doesn't
+    /// answer the probe before the timeout.
+    pub fn resync(&mut self) -> Result<()> {
+        self.probe()
+            .map_err(|err| err.into_error(GetStatus::FUNCTION, &[]))
+    }
+
+    /// Send a command chosen at runtime, and receive its response.
+    ///
+    /// This is the dynamic counterpart of [`transact()`](Supply::transact):
+    /// the response's type is only known once the command is, so it's
+    /// returned as an [`AnyResponse`].
+    pub fn transact_any(
+        &mut self,
+        command: &AnyCommand,
+    ) -> Result<AnyResponse> {
+        match command {
+            AnyCommand::GetSettings(c) => self.transact_as(c),
+            AnyCommand::GetStatus(c) => self.transact_as(c),
+            AnyCommand::GetVoltageLimit(c) => self.transact_as(c),
+            AnyCommand::GetCurrentLimit(c) => self.transact_as(c),
+            AnyCommand::GetCapabilities(c) => self.transact_as(c),
+            AnyCommand::GetPresets(c) => self.transact_as(c),
+            AnyCommand::SetVoltage(c) => self.transact_as(c),
+            AnyCommand::SetCurrent(c) => self.transact_as(c),
+            AnyCommand::SetVoltageLimit(c) => self.transact_as(c),
+            AnyCommand::SetCurrentLimit(c) => self.transact_as(c),
+            AnyCommand::SetOutput(c) => self.transact_as(c),
+            AnyCommand::SetPresets(c) => self.transact_as(c),
+            AnyCommand::SelectPreset(c) => self.transact_as(c),
+        }
+    }
+
+    /// Run the query with the given function, such as `"GETD"`.
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "serialport")]
+    /// use bk168xb::{transport::SerialTransport, Supply, BK1687B};
+    ///
+    /// # #[cfg(feature = "serialport")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let port = SerialTransport::open("/dev/ttyUSB0")?;
+    /// let mut supply = Supply::new(port, BK1687B);
+    ///
+    /// let function = std::env::args().nth(1).unwrap_or("GETD".into());
+    /// println!("{:?}", supply.query(&function)?);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "serialport"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// In addition to the usual communication failures, this returns a
+    /// [`Command`](Error::Command) error if `function` isn't a known command
+    /// which takes no arguments. Nothing is sent in that case.
+    pub fn query(&mut self, function: &str) -> Result<AnyResponse> {
+        let command = AnyCommand::from_function(function, self.variant)
+            .map_err(|source| Error::Command {
+                function: function.to_owned(),
+                source,
+            })?;
+
+        self.transact_any(&command)
+    }
+
+    fn transact_once<C: Command>(
+        &mut self,
+        command: &C,
+    ) -> Result<C::Response> {
+        let result = self.exchange(command);
+
+        if let Err(Failure::Response(err)) = &result {
+            if is_framing_error(err) && self.port.returns_when_idle() {
+                // The original error is more useful than any from resyncing.
+                let _ = self.resync();
+            }
+        }
+
+        result.map_err(|err| {
+            err.into_error(C::FUNCTION, self.port.last_received())
+        })
+    }
+
+    /// Back off, and discard anything which arrives in the meantime.
+    fn prepare_retry(&mut self, retry: u32) {
+        thread::sleep(self.policy.delay(retry));
+
+        // Any error will recur on the retry itself.
+        if self.port.returns_when_idle() {
+            let _ = self.port.drain();
+        }
+    }
+
+    fn exchange<C: Command>(
+        &mut self,
+        command: &C,
+    ) -> std::result::Result<C::Response, Failure> {
+        let sink = self.port.get_mut();
+        sink.send_command(command, self.variant)?;
+        sink.flush().map_err(crate::command::Error::from)?;
+
+        let response = self.port.get_response(self.variant)?;
+
+        Ok(response)
+    }
+
+    fn probe(&mut self) -> std::result::Result<(), Failure> {
+        self.port.drain()?;
+
+        let sink = self.port.get_mut();
+        sink.send_command(&GetStatus, self.variant)?;
+        sink.flush().map_err(crate::command::Error::from)?;
+
+        self.port.realign()?;
+
+        Ok(())
+    }
+
+    fn transact_as<C>(&mut self, command: &C) -> Result<AnyResponse>
+    where
+        C: Command,
+        C::Response: Into<AnyResponse>,
+    {
+        self.transact(command).map(Into::into)
+    }
+}
+
+/// Check that the settings after selecting a preset match it.
+pub(crate) fn check_recalled(
+    preset: PresetIndex,
+    expected: OperatingPoint,
+    actual: Settings,
+    variant: &SupplyVariant,
+) -> Result<Settings> {
+    let matches = SetVoltage(expected.voltage).is_applied(&actual, variant)
+        && SetCurrent(expected.current).is_applied(&actual, variant);
+
+    if !matches {
+        return Err(Error::PresetMismatch {
+            preset,
+            expected,
+            actual,
+        });
+    }
+
+    Ok(actual)
+}
+
+/// Whether a response error suggests the link is out of step.
+fn is_framing_error(err: &response::Error) -> bool {
+    matches!(
+        err,
+        response::Error::MalformedResponse
+            | response::Error::WrongLength { .. }
+    )
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command,
+        response::Error::{NoResponse, WrongLength},
+        sim::{Fault, Faulty, VirtualSupply},
+        supply::test_util::{BlockingPort, MockPort},
+        test_util::{any_psu, high_voltage_psu, low_voltage_psu},
+        OperatingPoint, OutputMode, BK1687B, BK1688B,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    test detects_variant() {
+        let port = MockPort::new("600500\rOK\r");
+        let supply = Supply::detect(port).unwrap();
+        expect_that!(&supply.variant(), eq(BK1685B));
+        expect_that!(&supply.get_ref().written_str(), eq("GMAX\r"));
+
+        let port = MockPort::new("361101\rOK\r");
+        let supply = Supply::detect(port).unwrap();
+        expect_that!(&supply.variant(), eq(BK1687B));
+
+        let port = MockPort::new("193209\rOK\r");
+        let supply = Supply::detect(port).unwrap();
+        expect_that!(&supply.variant(), eq(BK1688B));
+    }
+
+    test reports_unknown_variant() {
+        let mut port = MockPort::new("999999\rOK\r");
+
+        let err = Supply::detect(&mut port).unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::UnknownVariant [
+            eq(Capabilities { max_voltage: 99.9, max_current: 99.9 })
+        ]));
+        expect_that!(&port.written_str(), eq("GMAX\r"));
+    }
+
+    test reports_mismatched_current() {
+        // A 1687B's voltage, but a 1685B's current
+        let mut port = MockPort::new("360050\rOK\r");
+
+        let err = Supply::detect(&mut port).unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::UnknownVariant [
+            eq(Capabilities { max_voltage: 36.0, max_current: 5.0 })
+        ]));
+    }
+
+    test gets_status(any_psu) {
+        let port = MockPort::new("030201451\rOK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let status = supply.status().unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GETD\r"));
+        expect_that!(&status, eq(Status {
+            voltage: 3.02,
+            current: 1.45,
+            mode: OutputMode::ConstantCurrent,
+        }));
+    }
+
+    test gets_settings(low_voltage_psu) {
+        let port = MockPort::new("123456\rOK\r");
+        let mut supply = Supply::new(port, low_voltage_psu.val);
+
+        let settings = supply.settings().unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GETS\r"));
+        expect_that!(&settings, eq(Settings {
+            voltage: 12.3,
+            current: 45.6,
+        }));
+    }
+
+    test gets_limits(high_voltage_psu) {
+        let port = MockPort::new("123\rOK\r456\rOK\r");
+        let mut supply = Supply::new(port, high_voltage_psu.val);
+
+        let voltage = supply.voltage_limit().unwrap();
+        let current = supply.current_limit().unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GOVP\rGOCP\r"));
+        expect_that!(&voltage, eq(Voltage(12.3)));
+        expect_that!(&current, eq(Current(4.56)));
+    }
+
+    test gets_capabilities(any_psu) {
+        let port = MockPort::new("360100\rOK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let caps = supply.capabilities().unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GMAX\r"));
+        expect_that!(&caps, eq(Capabilities {
+            max_voltage: 36.0,
+            max_current: 10.0,
+        }));
+    }
+
+    test gets_presets(low_voltage_psu) {
+        let port = MockPort::new("050010\r125030\r150005\rOK\r");
+        let mut supply = Supply::new(port, low_voltage_psu.val);
+
+        let presets = supply.presets().unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GETM\r"));
+        expect_that!(&presets, eq(Presets(
+            OperatingPoint { voltage: 5.0, current: 1.0 },
+            OperatingPoint { voltage: 12.5, current: 3.0 },
+            OperatingPoint { voltage: 15.0, current: 0.5 },
+        )));
+    }
+
+    test sets_values(low_voltage_psu) {
+        let port = MockPort::new("OK\rOK\rOK\rOK\rOK\rOK\r");
+        let mut supply = Supply::new(port, low_voltage_psu.val);
+
+        supply.set_voltage(12.3).unwrap();
+        supply.set_current(Current(4.5)).unwrap();
+        supply.set_voltage_limit(Voltage(15.)).unwrap();
+        supply.set_current_limit(5.).unwrap();
+        supply.set_output(OutputState::On).unwrap();
+        supply.select_preset(PresetIndex::Three).unwrap();
+
+        expect_that!(
+            &supply.get_ref().written_str(),
+            eq("VOLT123\rCURR045\rSOVP150\rSOCP050\rSOUT0\rRUNM2\r")
+        );
+    }
+
+    test sets_presets(low_voltage_psu) {
+        let port = MockPort::new("OK\r");
+        let mut supply = Supply::new(port, low_voltage_psu.val);
+
+        supply.set_presets(Presets(
+            OperatingPoint { voltage: 1.1, current: 2.2 },
+            OperatingPoint { voltage: 3.3, current: 4.4 },
+            OperatingPoint { voltage: 5.5, current: 6.6 },
+        )).unwrap();
+
+        expect_that!(
+            &supply.get_ref().written_str(),
+            eq("PROM011022033044055066\r")
+        );
+    }
+
+    test transacts_generic_command(any_psu) {
+        let port = MockPort::new("123456780\rOK\rOK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let status: Status = supply.transact(&GetStatus).unwrap();
+        let ack: () = supply.transact(&SetOutput(OutputState::Off)).unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GETD\rSOUT1\r"));
+        expect_that!(&status, eq(Status {
+            voltage: 12.34,
+            current: 56.78,
+            mode: OutputMode::ConstantVoltage,
+        }));
+        expect_that!(&ack, eq(()));
+    }
+
+    test transacts_dynamic_command(any_psu) {
+        let port = MockPort::new("123456780\rOK\rOK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let status = supply.query("GETD").unwrap();
+        let command = AnyCommand::SetOutput(SetOutput(OutputState::Off));
+        let ack = supply.transact_any(&command).unwrap();
+
+        expect_that!(&supply.get_ref().written_str(), eq("GETD\rSOUT1\r"));
+        expect_that!(&status, eq(AnyResponse::Status(Status {
+            voltage: 12.34,
+            current: 56.78,
+            mode: OutputMode::ConstantVoltage,
+        })));
+        expect_that!(&ack, eq(AnyResponse::Ack(())));
+    }
+
+    test rejects_unknown_query(any_psu) {
+        let port = MockPort::new("OK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let err = supply.query("NOPE").unwrap_err();
+        assert_that!(&err, has_structure!(Error::Command {
+            source: is_variant!(command::Error::UnknownFunction)
+        }));
+
+        let err = supply.query("VOLT").unwrap_err();
+        assert_that!(&err, has_structure!(Error::Command {
+            source: is_variant!(command::Error::MalformedCommand)
+        }));
+
+        expect_that!(&supply.get_ref().written_str(), eq(""));
+    }
+
+    test reports_unrepresentable_command(any_psu) {
+        let port = MockPort::new("OK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let err = supply.set_voltage(-1.).unwrap_err();
+
+        assert_that!(&err, is_variant!(Error::Command));
+    }
+
+    test reports_missing_response(any_psu) {
+        let port = MockPort::new("");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let err = supply.status().unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(NoResponse)
+        }));
+    }
+
+    test reports_mismatched_response(any_psu) {
+        let port = MockPort::new("OK\r");
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let err = supply.settings().unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(WrongLength)
+        }));
+        expect_that!(&err.function(), eq(Some("GETS")));
+        expect_that!(&err.received(), eq(Some(&b"OK\r"[..])));
+        expect_that!(&err.is_malformed(), eq(true));
+    }
+
+    test retries_queries(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        port.inject(1, Fault::Truncate(4));
+        let mut supply = retrying(port, 2);
+
+        supply.status().unwrap();
+
+        // The truncated response is followed by a resync probe
+        expect_that!(&supply.get_ref().commands(), eq(4));
+    }
+
+    test gives_up_after_max_retries(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        port.inject(1, Fault::Drop);
+        let mut supply = retrying(port, 1);
+
+        let err = supply.settings().unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(NoResponse)
+        }));
+        expect_that!(&supply.get_ref().commands(), eq(2));
+    }
+
+    test doesnt_retry_unverifiable_commands(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        let mut supply = retrying(port, 3);
+
+        supply.select_preset(PresetIndex::Two).unwrap_err();
+
+        expect_that!(&supply.get_ref().commands(), eq(1));
+    }
+
+    test resends_output(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        let mut supply = retrying(port, 3);
+
+        supply.set_output(OutputState::On).unwrap();
+
+        let port = supply.get_ref();
+        expect_that!(&port.commands(), eq(2));
+        expect_that!(&port.get_ref().state().output, eq(OutputState::On));
+    }
+
+    test reads_back_before_retrying(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Drop);
+        let mut supply = retrying(port, 3);
+
+        supply.set_voltage(5.).unwrap();
+
+        // The voltage was set, so only the read-back follows
+        let port = supply.get_ref();
+        expect_that!(&port.commands(), eq(2));
+        expect_that!(&port.get_ref().state().settings.voltage, eq(5.));
+    }
+
+    test resends_unapplied_command(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::WriteError(io::ErrorKind::BrokenPipe));
+        let mut supply = retrying(port, 3);
+
+        supply.set_current_limit(1.5).unwrap();
+
+        let port = supply.get_ref();
+        expect_that!(&port.commands(), eq(3));
+        expect_that!(&port.get_ref().state().current_limit, eq(1.5));
+    }
+
+    test recalls_preset(any_psu) {
+        let variant = any_psu.val;
+        let mut supply = Supply::new(VirtualSupply::new(variant), variant);
+        let point = OperatingPoint { voltage: 3.3, current: 0.5 };
+        supply.get_mut().state_mut().presets[PresetIndex::Three] = point;
+
+        let settings = supply.recall_preset(PresetIndex::Three).unwrap();
+
+        expect_that!(&settings, eq(Settings { voltage: 3.3, current: 0.5 }));
+    }
+
+    test reports_preset_mismatch(low_voltage_psu) {
+        let port = MockPort::new(concat!(
+            "050010\r125030\r150005\rOK\r", // GETM
+            "OK\r",                          // RUNM
+            "050010\rOK\r",                  // GETS
+        ));
+        let mut supply = Supply::new(port, low_voltage_psu.val);
+
+        let err = supply.recall_preset(PresetIndex::Two).unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::PresetMismatch {
+            preset: eq(PresetIndex::Two),
+            expected: eq(OperatingPoint { voltage: 12.5, current: 3. }),
+            actual: eq(Settings { voltage: 5., current: 1. })
+        }));
+        let written = supply.get_ref().written_str();
+        expect_that!(&written, eq("GETM\rRUNM1\rGETS\r"));
+    }
+
+    test resyncs_after_garbage(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        // Garble the reply's terminator, so it's only given up on once the
+        // port goes quiet
+        port.inject(0, Fault::Corrupt(10));
+        let mut supply = Supply::new(port, any_psu.val);
+
+        let err = supply.status().unwrap_err();
+        expect_that!(&err.is_malformed(), eq(true));
+
+        supply.settings().unwrap();
+
+        // The resync's probe comes between the two
+        expect_that!(&supply.get_ref().commands(), eq(3));
+    }
+
+    test retries_after_resync(any_psu) {
+        let mut port = Faulty::new(VirtualSupply::new(any_psu.val));
+        port.inject(0, Fault::Corrupt(10));
+        let mut supply = retrying(port, 1);
+
+        supply.status().unwrap();
+
+        expect_that!(&supply.get_ref().commands(), eq(3));
+    }
+
+    test doesnt_resync_blocking_port(any_psu) {
+        let port = BlockingPort(MockPort::new("OK\r"));
+        let mut supply = Supply::new(port, any_psu.val);
+
+        // If this blocks, the test fails rather than hanging
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(supply.settings().map(|_| ()));
+        });
+        let result = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        assert_that!(&result.unwrap_err(), has_structure!(Error::Response {
+            source: is_variant!(WrongLength)
+        }));
+    }
+
+    fn retrying(
+        port: Faulty<VirtualSupply>,
+        max_retries: u32,
+    ) -> Supply<Faulty<VirtualSupply>> {
+        let variant = port.get_ref().variant();
+        let mut supply = Supply::new(port, variant);
+        supply.set_retry_policy(RetryPolicy {
+            timeout: Duration::from_millis(20),
+            max_retries,
+            backoff: Duration::ZERO,
+        });
+
+        supply
+    }
+}
@@ -0,0 +1,6 @@
+//! Error handling for supply transactions
+//!
+//! Supplies report the crate-wide [`Error`](crate::Error); it's re-exported
+//! here for compatibility.
+
+pub use crate::error::{Error, Result};
@@ -0,0 +1,106 @@
+use crate::{command, response::DEFAULT_TIMEOUT, Error};
+
+use std::time::Duration;
+
+/// How hard to try to complete a transaction.
+///
+/// A transaction which fails for want of a valid response is retried, up to
+/// `max_retries` times, if that's known to be safe:
+///
+/// - [Idempotent](crate::command::Command::IDEMPOTENT) commands, such as
+///   queries, are simply sent again
+/// - Commands which can be [read back](crate::command::ReadBack) are only sent
+///   again if reading back shows that they didn't take effect
+/// - Other commands are never retried
+///
+/// ```
+/// use bk168xb::supply::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy {
+///     max_retries: 3,
+///     ..RetryPolicy::default()
+/// };
+/// assert_eq!(policy.delay(2), policy.backoff * 4);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RetryPolicy {
+    /// How long to wait for each command's response.
+    pub timeout: Duration,
+
+    /// How many times a failed transaction may be retried.
+    pub max_retries: u32,
+
+    /// How long to wait before the first retry.
+    ///
+    /// The wait doubles with each subsequent retry. Any input which arrives
+    /// during the wait is discarded, so that a late response isn't mistaken
+    /// for the response to the retry.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// How long to wait before retry number `retry`, counting from zero.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1_u32.checked_shl(retry).unwrap_or(u32::MAX);
+
+        self.backoff.saturating_mul(factor)
+    }
+
+    /// Whether a failed transaction may be retried, if the command allows.
+    ///
+    /// Only failures which might not recur are retried: those to do with the
+    /// response, and failures to write the command.
+    pub(crate) fn may_retry(&self, err: &Error, retry: u32) -> bool {
+        let transient = match err {
+            Error::Response { .. } => true,
+            Error::Command { source, .. } => {
+                matches!(source, command::Error::WriteFailure(_))
+            }
+            _ => false,
+        };
+
+        transient && retry < self.max_retries
+    }
+}
+
+impl Default for RetryPolicy {
+    /// The [`DEFAULT_TIMEOUT`], and no retries.
+    fn default() -> Self {
+        RetryPolicy {
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: 0,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use galvanic_assert::{assert_that, matchers::*};
+
+    test doubles_delay() {
+        let policy = RetryPolicy {
+            backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        };
+
+        assert_that!(&policy.delay(0), eq(Duration::from_millis(10)));
+        assert_that!(&policy.delay(1), eq(Duration::from_millis(20)));
+        assert_that!(&policy.delay(3), eq(Duration::from_millis(80)));
+    }
+
+    test saturates_delay() {
+        let policy = RetryPolicy {
+            backoff: Duration::MAX / 2,
+            ..RetryPolicy::default()
+        };
+
+        assert_that!(&policy.delay(2), eq(Duration::MAX));
+        assert_that!(&policy.delay(40), eq(Duration::MAX));
+    }
+}
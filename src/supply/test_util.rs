@@ -0,0 +1,73 @@
+//! Helpers for exercising a `Supply` without hardware.
+
+use std::{
+    io::{self, Read, Write},
+    thread,
+};
+
+/// A fake serial port.
+///
+/// Records everything written to it, and serves a canned series of bytes to
+/// readers.
+#[derive(Debug)]
+pub struct MockPort {
+    pub written: Vec<u8>,
+    to_read: io::Cursor<Vec<u8>>,
+}
+
+impl MockPort {
+    pub fn new(to_read: &str) -> Self {
+        MockPort {
+            written: Vec::new(),
+            to_read: io::Cursor::new(to_read.as_bytes().to_vec()),
+        }
+    }
+
+    pub fn written_str(&self) -> &str {
+        std::str::from_utf8(&self.written).unwrap()
+    }
+}
+
+impl Read for MockPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.to_read.read(buf)
+    }
+}
+
+impl Write for MockPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A fake serial port without a read timeout.
+///
+/// Like a [`MockPort`], but once its canned bytes run out, reads block
+/// forever.
+#[derive(Debug)]
+pub struct BlockingPort(pub MockPort);
+
+impl Read for BlockingPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => loop {
+                thread::park();
+            },
+            count => Ok(count),
+        }
+    }
+}
+
+impl Write for BlockingPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
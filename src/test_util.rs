@@ -39,9 +39,9 @@ fixture! {
                 -1.,
                 100.,
                 101.,
-                std::f32::NAN,
-                std::f32::INFINITY,
-                std::f32::NEG_INFINITY,
+                f32::NAN,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
             ].into_iter()
         }
         setup (&mut self) {
@@ -57,9 +57,9 @@ fixture! {
                 -1.,
                 100.,
                 101.,
-                std::f32::NAN,
-                std::f32::INFINITY,
-                std::f32::NEG_INFINITY,
+                f32::NAN,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
             ].into_iter()
         }
         setup (&mut self) {
@@ -75,9 +75,9 @@ fixture! {
                 -1.,
                 10.0,
                 10.1,
-                std::f32::NAN,
-                std::f32::INFINITY,
-                std::f32::NEG_INFINITY,
+                f32::NAN,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
             ].into_iter()
         }
         setup (&mut self) {
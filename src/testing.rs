@@ -0,0 +1,345 @@
+//! Test doubles for code built on this crate
+//!
+//! [`MockSupply`] stands in for a supply's serial port. Tests script the
+//! exchange they expect, in terms of typed commands and responses, and the
+//! mock checks that the code under test sends exactly those commands:
+//!
+//! ```
+//! use bk168xb::{
+//!     command::{GetStatus, SetVoltage},
+//!     response::Status,
+//!     testing::MockSupply,
+//!     OutputMode, Supply, BK1687B,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut mock = MockSupply::new(BK1687B);
+//! mock.expect(&SetVoltage(5.), ()).expect(
+//!     &GetStatus,
+//!     Status {
+//!         voltage: 5.,
+//!         current: 0.25,
+//!         mode: OutputMode::ConstantVoltage,
+//!     },
+//! );
+//!
+//! let mut supply = Supply::new(mock, BK1687B);
+//! supply.set_voltage(5.)?;
+//! assert_eq!(supply.status()?.current, 0.25);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module requires the `testing` feature.
+
+use crate::{
+    command::{AnyCommand, Command, CommandSink},
+    core::escape,
+    response::Response,
+    SupplyVariant,
+};
+
+use std::{collections::VecDeque, io, thread};
+
+/// A scripted stand-in for a supply.
+///
+/// Each expectation pairs a command with the reply the mock gives when it
+/// arrives. Expectations are met in the order they were declared.
+///
+/// # Panics
+///
+/// The mock panics (failing the test) when:
+///
+/// - It receives a command other than the next one expected. The panic
+///   message shows both, decoded and as raw bytes.
+/// - It receives a command after every expectation has been met.
+/// - It's dropped before every expectation has been met, unless the thread is
+///   already panicking.
+///
+/// Expectations are checked as soon as each command's terminating
+/// carriage-return is written, so the panic points at the offending call.
+#[derive(Debug)]
+pub struct MockSupply {
+    variant: &'static SupplyVariant,
+    expected: VecDeque<Expectation>,
+    received: Vec<u8>,
+    reply: VecDeque<u8>,
+    error: Option<io::ErrorKind>,
+    met: usize,
+}
+
+/// A command, and how to answer it.
+#[derive(Debug)]
+struct Expectation {
+    command: Vec<u8>,
+    reply: Reply,
+}
+
+#[derive(Debug)]
+enum Reply {
+    Bytes(Vec<u8>),
+    Error(io::ErrorKind),
+}
+
+impl MockSupply {
+    /// Create a mock with no expectations.
+    ///
+    /// The mock encodes and decodes values the way `variant` does.
+    pub fn new(variant: &'static SupplyVariant) -> Self {
+        MockSupply {
+            variant,
+            expected: VecDeque::new(),
+            received: Vec::new(),
+            reply: VecDeque::new(),
+            error: None,
+            met: 0,
+        }
+    }
+
+    /// Expect `command`, and answer it with `response`.
+    ///
+    /// # Panics
+    ///
+    /// If `command` or `response` can't be represented on the wire.
+    pub fn expect<C: Command>(
+        &mut self,
+        command: &C,
+        response: C::Response,
+    ) -> &mut Self {
+        let mut raw = Vec::new();
+        response
+            .serialize(&mut raw, self.variant)
+            .expect("expected response can't be serialized");
+
+        self.push(command, Reply::Bytes(raw))
+    }
+
+    /// Expect `command`, and answer it with arbitrary bytes.
+    ///
+    /// This is useful for exercising handling of malformed responses.
+    pub fn expect_raw<C: Command>(
+        &mut self,
+        command: &C,
+        response: &[u8],
+    ) -> &mut Self {
+        self.push(command, Reply::Bytes(response.to_vec()))
+    }
+
+    /// Expect `command`, and never answer it.
+    pub fn expect_no_response<C: Command>(&mut self, command: &C) -> &mut Self {
+        self.push(command, Reply::Bytes(Vec::new()))
+    }
+
+    /// Expect `command`, and fail the next read with an I/O error.
+    pub fn expect_read_error<C: Command>(
+        &mut self,
+        command: &C,
+        kind: io::ErrorKind,
+    ) -> &mut Self {
+        self.push(command, Reply::Error(kind))
+    }
+
+    /// Whether every expectation has been met.
+    pub fn is_done(&self) -> bool {
+        self.expected.is_empty()
+    }
+
+    /// Panic unless every expectation has been met.
+    pub fn assert_done(&self) {
+        if let Some(next) = self.expected.front() {
+            panic!(
+                "{} expected command(s) never sent, starting with {}",
+                self.expected.len(),
+                self.describe(&next.command)
+            );
+        }
+    }
+
+    fn push<C: Command>(&mut self, command: &C, reply: Reply) -> &mut Self {
+        let mut raw = Vec::new();
+        raw.send_command(command, self.variant)
+            .expect("expected command can't be serialized");
+
+        self.expected.push_back(Expectation {
+            command: raw,
+            reply,
+        });
+
+        self
+    }
+
+    /// Check a complete command against the next expectation.
+    fn receive(&mut self, command: Vec<u8>) {
+        let index = self.met + 1;
+        let expectation = match self.expected.pop_front() {
+            Some(expectation) => expectation,
+            None => panic!(
+                "command #{} was unexpected\n     got: {}",
+                index,
+                self.describe(&command)
+            ),
+        };
+
+        if expectation.command != command {
+            panic!(
+                "command #{} didn't match\nexpected: {}\n     got: {}",
+                index,
+                self.describe(&expectation.command),
+                self.describe(&command)
+            );
+        }
+
+        self.met += 1;
+        match expectation.reply {
+            Reply::Bytes(raw) => self.reply.extend(raw),
+            Reply::Error(kind) => self.error = Some(kind),
+        }
+    }
+
+    /// Show a command both decoded and as raw bytes.
+    fn describe(&self, raw: &[u8]) -> String {
+        match AnyCommand::decode(raw, self.variant) {
+            Ok(command) => format!("{:?} \"{}\"", command, escape(raw)),
+            Err(_) => format!("\"{}\"", escape(raw)),
+        }
+    }
+}
+
+impl io::Read for MockSupply {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(kind) = self.error.take() {
+            return Err(kind.into());
+        }
+
+        let count = buf.len().min(self.reply.len());
+        for (dst, src) in buf.iter_mut().zip(self.reply.drain(..count)) {
+            *dst = src;
+        }
+
+        Ok(count)
+    }
+}
+
+impl io::Write for MockSupply {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.received.push(byte);
+            if byte == b'\r' {
+                let command = std::mem::take(&mut self.received);
+                self.receive(command);
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MockSupply {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.assert_done();
+        }
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command::{GetSettings, SetOutput, SetVoltage},
+        response::{self, Error::MalformedResponse, Settings},
+        supply::{Error, Supply},
+        OutputState, BK1687B,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn panic_message<F: FnOnce()>(f: F) -> String {
+        let err = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        err.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    test answers_expected_commands() {
+        let mut mock = MockSupply::new(BK1687B);
+        mock.expect(&SetOutput(OutputState::On), ())
+            .expect(&GetSettings, Settings { voltage: 3.3, current: 0.5 });
+        let mut supply = Supply::new(mock, BK1687B);
+
+        supply.set_output(OutputState::On).unwrap();
+        let settings = supply.settings().unwrap();
+
+        expect_that!(&settings, eq(Settings { voltage: 3.3, current: 0.5 }));
+        expect_that!(&supply.get_ref().is_done(), eq(true));
+    }
+
+    test shows_diff_on_mismatch() {
+        let mut mock = MockSupply::new(BK1687B);
+        mock.expect(&SetVoltage(5.), ());
+        let mut supply = Supply::new(mock, BK1687B);
+
+        let message = panic_message(|| {
+            let _ = supply.set_voltage(5.5);
+        });
+
+        expect_that!(&message.as_str(), eq(
+            "command #1 didn't match\n\
+             expected: SetVoltage(SetVoltage(5.0)) \"VOLT050\\r\"\n     \
+             got: SetVoltage(SetVoltage(5.5)) \"VOLT055\\r\""
+        ));
+    }
+
+    test rejects_unexpected_command() {
+        let mut supply = Supply::new(MockSupply::new(BK1687B), BK1687B);
+
+        let message = panic_message(|| {
+            let _ = supply.settings();
+        });
+
+        expect_that!(&message.as_str(), eq(
+            "command #1 was unexpected\n     \
+             got: GetSettings(GetSettings) \"GETS\\r\""
+        ));
+    }
+
+    test reports_unmet_expectations() {
+        let mut mock = MockSupply::new(BK1687B);
+        mock.expect(&GetSettings, Settings { voltage: 0., current: 0. });
+
+        let message = panic_message(|| drop(mock));
+
+        expect_that!(&message.as_str(), eq(
+            "1 expected command(s) never sent, \
+             starting with GetSettings(GetSettings) \"GETS\\r\""
+        ));
+    }
+
+    test serves_malformed_and_missing_responses() {
+        let mut mock = MockSupply::new(BK1687B);
+        mock.expect_raw(&GetSettings, b"12\rOK\r")
+            .expect_no_response(&GetSettings)
+            .expect_read_error(&GetSettings, io::ErrorKind::BrokenPipe);
+        let mut supply = Supply::new(mock, BK1687B);
+        supply.set_timeout(std::time::Duration::ZERO);
+
+        assert_that!(&supply.settings().unwrap_err(), has_structure!(
+            Error::Response { source: is_variant!(MalformedResponse) }
+        ));
+        assert_that!(&supply.settings().unwrap_err(), has_structure!(
+            Error::Response { source: is_variant!(response::Error::NoResponse) }
+        ));
+        assert_that!(&supply.settings().unwrap_err(), has_structure!(
+            Error::Response { source: is_variant!(response::Error::ReadFailure) }
+        ));
+    }
+}
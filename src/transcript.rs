@@ -0,0 +1,21 @@
+//! Captured supply traffic
+//!
+//! A transcript is a text log of the bytes exchanged with a supply, one chunk
+//! per line:
+//!
+//! ```text
+//! 0.000000 TX GETD\r
+//! 0.003182 RX 030201450\rOK\r
+//! ```
+//!
+//! Each line holds a timestamp (in seconds, relative to the start of the
+//! capture), the direction of the traffic (`TX` from the host to the supply,
+//! and `RX` from the supply to the host), and the bytes themselves. Bytes are
+//! escaped so that the log remains readable: `\r`, `\n`, and `\\` stand for
+//! themselves, and any other unprintable byte is written as `\xNN`.
+
+mod decode;
+mod error;
+mod record;
+
+pub use self::{decode::*, error::*, record::*};
@@ -0,0 +1,335 @@
+use crate::{
+    command::{self, AnyCommand},
+    response::{self, frame_end, AnyResponse},
+    transcript::{escape, Direction, Record},
+    SupplyVariant,
+};
+
+use std::{collections::VecDeque, fmt, time::Duration};
+
+/// A single decoded command or response.
+#[derive(Debug)]
+pub struct Event {
+    /// When the event's last byte was captured.
+    pub time: Duration,
+
+    /// Which way the event's bytes were flowing.
+    pub direction: Direction,
+
+    /// The bytes making up the event.
+    pub raw: Vec<u8>,
+
+    /// What the bytes mean.
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Whether this event represents a protocol error.
+    pub fn is_error(&self) -> bool {
+        !matches!(self.kind, EventKind::Command(_) | EventKind::Response(_))
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let raw = escape(&self.raw);
+        write!(
+            f,
+            "{}.{:06} {} {:<26} ",
+            self.time.as_secs(),
+            self.time.subsec_micros(),
+            self.direction,
+            raw
+        )?;
+
+        match &self.kind {
+            EventKind::Command(command) => write!(f, "{:?}", command),
+            EventKind::Response(response) => write!(f, "{:?}", response),
+            EventKind::BadCommand(err) => write!(f, "error: {}", err),
+            EventKind::BadResponse(err) => write!(f, "error: {}", err),
+            EventKind::Unsolicited => {
+                f.write_str("error: response without a command")
+            }
+            EventKind::Incomplete => f.write_str("error: incomplete"),
+            EventKind::Unanswered => f.write_str("error: no response"),
+        }
+    }
+}
+
+/// The meaning of an [`Event`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// A valid command.
+    Command(AnyCommand),
+
+    /// A valid response to the preceding command.
+    Response(AnyResponse),
+
+    /// A complete command, which couldn't be decoded.
+    BadCommand(command::Error),
+
+    /// A complete response, which didn't match the format of the preceding
+    /// command's response.
+    BadResponse(response::Error),
+
+    /// A response which arrived without a command awaiting it.
+    Unsolicited,
+
+    /// Bytes at the end of the transcript, which don't form a complete
+    /// command or response.
+    Incomplete,
+
+    /// A command which was never answered.
+    Unanswered,
+}
+
+/// Decode captured traffic into commands and responses.
+///
+/// Records should be ordered by time. Transmitted bytes are split into
+/// commands at each carriage-return, and received bytes into responses at each
+/// `"OK\r"`. Responses are matched up with commands in the order they were
+/// sent.
+///
+/// Where traffic was captured separately in each direction (with no
+/// timestamps to interleave it), all transmitted records may be passed before
+/// all received records.
+///
+/// ```
+/// use bk168xb::{
+///     command::{AnyCommand, GetVoltageLimit},
+///     response::{AnyResponse, Voltage},
+///     transcript::{self, EventKind},
+///     BK1687B,
+/// };
+///
+/// let log = "0.000000 TX GOVP\\r\n0.002000 RX 12\n0.003000 RX 3\\rOK\\r\n";
+/// let records = transcript::read(log.as_bytes()).unwrap();
+///
+/// let events = transcript::decode(&records, BK1687B);
+/// assert!(matches!(
+///     events[0].kind,
+///     EventKind::Command(AnyCommand::GetVoltageLimit(GetVoltageLimit))
+/// ));
+/// assert!(matches!(
+///     events[1].kind,
+///     EventKind::Response(AnyResponse::Voltage(Voltage(v))) if v == 12.3
+/// ));
+/// ```
+pub fn decode(records: &[Record], variant: &SupplyVariant) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut tx = Vec::new();
+    let mut rx = Vec::new();
+    let mut pending = VecDeque::new();
+    let mut last_time = Duration::ZERO;
+
+    for record in records {
+        let time = record.time;
+        last_time = time;
+
+        match record.direction {
+            Direction::Tx => {
+                for &byte in &record.bytes {
+                    tx.push(byte);
+                    if byte != b'\r' {
+                        continue;
+                    }
+
+                    let raw = std::mem::take(&mut tx);
+                    let kind = match AnyCommand::decode(&raw, variant) {
+                        Ok(command) => {
+                            pending.push_back((time, raw.clone(), command));
+                            EventKind::Command(command)
+                        }
+                        Err(err) => EventKind::BadCommand(err),
+                    };
+
+                    events.push(Event {
+                        time,
+                        direction: Direction::Tx,
+                        raw,
+                        kind,
+                    });
+                }
+            }
+            Direction::Rx => {
+                rx.extend_from_slice(&record.bytes);
+
+                while let Some(end) = frame_end(&rx) {
+                    let raw: Vec<_> = rx.drain(..end).collect();
+                    let kind = match pending.pop_front() {
+                        Some((_, _, command)) => {
+                            match AnyResponse::parse(&command, &raw, variant) {
+                                Ok(response) => EventKind::Response(response),
+                                Err(err) => EventKind::BadResponse(err),
+                            }
+                        }
+                        None => EventKind::Unsolicited,
+                    };
+
+                    events.push(Event {
+                        time,
+                        direction: Direction::Rx,
+                        raw,
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+
+    for (direction, raw) in [(Direction::Tx, tx), (Direction::Rx, rx)] {
+        if !raw.is_empty() {
+            events.push(Event {
+                time: last_time,
+                direction,
+                raw,
+                kind: EventKind::Incomplete,
+            });
+        }
+    }
+
+    for (time, raw, _) in pending {
+        events.push(Event {
+            time,
+            direction: Direction::Tx,
+            raw,
+            kind: EventKind::Unanswered,
+        });
+    }
+
+    events
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        command::{GetStatus, SetVoltage},
+        response::Status,
+        test_util::{any_psu, low_voltage_psu},
+        OutputMode,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+
+    fn record(micros: u64, direction: Direction, bytes: &str) -> Record {
+        Record {
+            time: Duration::from_micros(micros),
+            direction,
+            bytes: bytes.as_bytes().to_vec(),
+        }
+    }
+
+    test decodes_exchanges(low_voltage_psu) {
+        let records = vec![
+            record(0, Direction::Tx, "GETD\r"),
+            record(1000, Direction::Rx, "0302"),
+            record(2000, Direction::Rx, "01451\rOK\r"),
+            record(3000, Direction::Tx, "VOLT050\r"),
+            record(4000, Direction::Rx, "OK\r"),
+        ];
+
+        let events = decode(&records, low_voltage_psu.val);
+
+        expect_that!(&events.len(), eq(4));
+        assert_that!(&events[0].kind, has_structure!(EventKind::Command [
+            eq(AnyCommand::GetStatus(GetStatus))
+        ]));
+        assert_that!(&events[1].kind, has_structure!(EventKind::Response [
+            eq(AnyResponse::Status(Status {
+                voltage: 3.02,
+                current: 1.45,
+                mode: OutputMode::ConstantCurrent,
+            }))
+        ]));
+        expect_that!(&events[1].time, eq(Duration::from_micros(2000)));
+        expect_that!(&events[1].raw, eq(b"030201451\rOK\r".to_vec()));
+        assert_that!(&events[2].kind, has_structure!(EventKind::Command [
+            eq(AnyCommand::SetVoltage(SetVoltage(5.)))
+        ]));
+        assert_that!(&events[3].kind, has_structure!(EventKind::Response [
+            eq(AnyResponse::Ack(()))
+        ]));
+        expect_that!(&events.iter().any(Event::is_error), eq(false));
+    }
+
+    test pairs_separate_captures(any_psu) {
+        let records = vec![
+            record(0, Direction::Tx, "SOUT0\rGETS\r"),
+            record(0, Direction::Rx, "OK\r000000\rOK\r"),
+        ];
+
+        let events = decode(&records, any_psu.val);
+
+        let directions: Vec<_> = events.iter().map(|e| e.direction).collect();
+        expect_that!(&directions, eq(vec![
+            Direction::Tx,
+            Direction::Tx,
+            Direction::Rx,
+            Direction::Rx,
+        ]));
+        expect_that!(&events.iter().any(Event::is_error), eq(false));
+    }
+
+    test flags_framing_errors(any_psu) {
+        let records = vec![
+            record(0, Direction::Rx, "OK\r"),
+            record(1, Direction::Tx, "BOGUS\r"),
+            record(2, Direction::Tx, "GETD\r"),
+            record(3, Direction::Rx, "123\rOK\r"),
+            record(4, Direction::Tx, "GETS\r"),
+            record(5, Direction::Tx, "GOV"),
+            record(6, Direction::Rx, "00"),
+        ];
+
+        let events = decode(&records, any_psu.val);
+        let kinds: Vec<_> = events.iter().map(|e| &e.kind).collect();
+
+        expect_that!(&kinds.len(), eq(8));
+        assert_that!(&kinds[0], is_variant!(EventKind::Unsolicited));
+        assert_that!(&kinds[1], is_variant!(EventKind::BadCommand));
+        assert_that!(&kinds[2], is_variant!(EventKind::Command));
+        assert_that!(&kinds[3], is_variant!(EventKind::BadResponse));
+        assert_that!(&kinds[4], is_variant!(EventKind::Command));
+        assert_that!(&kinds[5], is_variant!(EventKind::Incomplete));
+        expect_that!(&events[5].direction, eq(Direction::Tx));
+        assert_that!(&kinds[6], is_variant!(EventKind::Incomplete));
+        expect_that!(&events[6].direction, eq(Direction::Rx));
+        assert_that!(&kinds[7], is_variant!(EventKind::Unanswered));
+        expect_that!(&events.iter().filter(|e| e.is_error()).count(), eq(6));
+    }
+
+    test flags_unanswered_command(any_psu) {
+        let records = vec![record(0, Direction::Tx, "GETS\r")];
+
+        let events = decode(&records, any_psu.val);
+
+        expect_that!(&events.len(), eq(2));
+        assert_that!(&events[1].kind, is_variant!(EventKind::Unanswered));
+        expect_that!(&events[1].raw, eq(b"GETS\r".to_vec()));
+    }
+
+    test displays_events(low_voltage_psu) {
+        let records = vec![
+            record(0, Direction::Tx, "GOVP\r"),
+            record(12, Direction::Rx, "123\rOK\r"),
+        ];
+
+        let events = decode(&records, low_voltage_psu.val);
+        let lines: Vec<_> = events.iter().map(ToString::to_string).collect();
+
+        expect_that!(&lines[0].as_str(), eq(
+            "0.000000 TX GOVP\\r                     GetVoltageLimit(GetVoltageLimit)"
+        ));
+        expect_that!(&lines[1].as_str(), eq(
+            "0.000012 RX 123\\rOK\\r                  Voltage(Voltage(12.3))"
+        ));
+    }
+}
@@ -0,0 +1,36 @@
+//! Errors that can arise from reading transcripts
+
+use std::io;
+
+/// Errors that can arise from reading a transcript.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A line of the transcript didn't match the expected format.
+    #[error("invalid transcript line {line}: {source}")]
+    InvalidLine {
+        /// The (one-based) number of the offending line.
+        line: usize,
+
+        /// What was wrong with it.
+        source: ParseRecordError,
+    },
+
+    /// The source returned an error when reading the transcript.
+    #[error("failed to read transcript")]
+    ReadFailure(#[from] io::Error),
+}
+
+/// Why a line couldn't be parsed as a [`Record`](crate::transcript::Record).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, thiserror::Error)]
+#[error("{reason} at column {column}")]
+pub struct ParseRecordError {
+    /// What was wrong, such as `"invalid direction"`.
+    pub reason: &'static str,
+
+    /// The (one-based) column at which the problem starts.
+    pub column: usize,
+}
+
+/// A specialized `Result` type for transcript operations.
+pub type Result<T> = std::result::Result<T, Error>;
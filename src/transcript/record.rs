@@ -0,0 +1,271 @@
+use crate::transcript::{Error, ParseRecordError, Result};
+
+use std::{
+    fmt,
+    io::{self, BufRead},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Which way traffic was flowing.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    /// From the host to the supply.
+    Tx,
+
+    /// From the supply to the host.
+    Rx,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Tx => f.write_str("TX"),
+            Direction::Rx => f.write_str("RX"),
+        }
+    }
+}
+
+/// A chunk of bytes captured in a transcript.
+///
+/// Records display as (and parse from) a single transcript line, without its
+/// trailing newline.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Record {
+    /// When the bytes were captured, relative to the start of the capture.
+    pub time: Duration,
+
+    /// Which way the bytes were flowing.
+    pub direction: Direction,
+
+    /// The captured bytes.
+    pub bytes: Vec<u8>,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} {} {}",
+            self.time.as_secs(),
+            self.time.subsec_micros(),
+            self.direction,
+            escape(&self.bytes)
+        )
+    }
+}
+
+impl FromStr for Record {
+    type Err = ParseRecordError;
+
+    fn from_str(line: &str) -> std::result::Result<Self, ParseRecordError> {
+        // Columns count characters, not bytes.
+        let error = |reason, offset: usize| ParseRecordError {
+            reason,
+            column: line[..offset].chars().count() + 1,
+        };
+
+        let mut fields = line.splitn(3, ' ');
+        let time = fields.next().unwrap_or("");
+        let direction_at = time.len() + 1;
+        let direction = fields
+            .next()
+            .ok_or_else(|| error("missing direction", line.len()))?;
+        let bytes_at = direction_at + direction.len() + 1;
+        let bytes = fields.next().unwrap_or("");
+
+        let invalid_time = || error("invalid timestamp", 0);
+        let (secs, micros) = time.split_once('.').ok_or_else(invalid_time)?;
+        if micros.len() != 6 {
+            return Err(invalid_time());
+        }
+        let secs = secs.parse::<u64>().map_err(|_| invalid_time())?;
+        let micros = micros.parse::<u32>().map_err(|_| invalid_time())?;
+        let time = Duration::new(secs, micros * 1000);
+
+        let direction = match direction {
+            "TX" => Direction::Tx,
+            "RX" => Direction::Rx,
+            _ => return Err(error("invalid direction", direction_at)),
+        };
+
+        let bytes = unescape_at(bytes).map_err(|offset| {
+            error("invalid escape sequence", bytes_at + offset)
+        })?;
+
+        Ok(Record {
+            time,
+            direction,
+            bytes,
+        })
+    }
+}
+
+/// Read every record from a transcript.
+///
+/// Blank lines are ignored.
+pub fn read<R: BufRead>(source: R) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = line.parse().map_err(|source| Error::InvalidLine {
+            line: i + 1,
+            source,
+        })?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Write a single record to a transcript.
+pub fn write<W: io::Write>(sink: &mut W, record: &Record) -> io::Result<()> {
+    writeln!(sink, "{}", record)
+}
+
+/// Escape bytes for inclusion in a transcript.
+pub fn escape(bytes: &[u8]) -> String {
+    crate::core::escape(bytes)
+}
+
+/// Reverse [`escape()`].
+///
+/// Returns `None` if `escaped` contains an invalid escape sequence.
+pub fn unescape(escaped: &str) -> Option<Vec<u8>> {
+    unescape_at(escaped).ok()
+}
+
+/// Reverse [`escape()`], or find the offset of the first invalid escape
+/// sequence.
+fn unescape_at(escaped: &str) -> std::result::Result<Vec<u8>, usize> {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut chars = escaped.bytes().enumerate();
+
+    while let Some((offset, byte)) = chars.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let mut next = || chars.next().map(|(_, byte)| byte).ok_or(offset);
+        let unescaped = match next()? {
+            b'r' => b'\r',
+            b'n' => b'\n',
+            b'\\' => b'\\',
+            b'x' => {
+                let hex = [next()?, next()?];
+                std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or(offset)?
+            }
+            _ => return Err(offset),
+        };
+        bytes.push(unescaped);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        matchers::*, structure,
+    };
+
+    test escapes_unprintable_bytes() {
+        let escaped = escape(b"GETD\r030201450\rOK\r\n\\\x00\xff");
+
+        expect_that!(
+            &escaped.as_str(),
+            eq("GETD\\r030201450\\rOK\\r\\n\\\\\\x00\\xff")
+        );
+    }
+
+    test unescapes_what_it_escapes() {
+        let raw: Vec<u8> = (0..=255).collect();
+
+        expect_that!(&unescape(&escape(&raw)), eq(Some(raw)));
+    }
+
+    test rejects_invalid_escapes() {
+        expect_that!(&unescape("\\q"), eq(None));
+        expect_that!(&unescape("\\x0"), eq(None));
+        expect_that!(&unescape("\\xzz"), eq(None));
+        expect_that!(&unescape("trailing\\"), eq(None));
+    }
+
+    test formats_record() {
+        let record = Record {
+            time: Duration::from_micros(12_000_345),
+            direction: Direction::Tx,
+            bytes: b"VOLT123\r".to_vec(),
+        };
+
+        expect_that!(
+            &record.to_string(),
+            eq("12.000345 TX VOLT123\\r".to_owned())
+        );
+        expect_that!(&record.to_string().parse(), eq(Ok(record)));
+    }
+
+    test reads_transcript() {
+        let log = "0.000000 TX GETD\\r\n\n0.003182 RX 030201450\\rOK\\r\n";
+
+        let records = read(log.as_bytes()).unwrap();
+
+        expect_that!(&records, eq(vec![
+            Record {
+                time: Duration::ZERO,
+                direction: Direction::Tx,
+                bytes: b"GETD\r".to_vec(),
+            },
+            Record {
+                time: Duration::from_micros(3182),
+                direction: Direction::Rx,
+                bytes: b"030201450\rOK\r".to_vec(),
+            },
+        ]));
+    }
+
+    test reports_invalid_line() {
+        let log = "0.000000 TX GETD\\r\n0.1 RX OK\\r\n";
+
+        let err = read(log.as_bytes()).unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::InvalidLine {
+            line: eq(2),
+            source: eq(ParseRecordError {
+                reason: "invalid timestamp",
+                column: 1,
+            })
+        }));
+    }
+
+    test reports_where_line_is_invalid() {
+        let cases = [
+            ("0.000000", "missing direction", 9),
+            ("0.1 TX GETD\\r", "invalid timestamp", 1),
+            ("0.000000 XX GETD\\r", "invalid direction", 10),
+            ("0.000000 TX GETD\\q", "invalid escape sequence", 17),
+            ("0.000000 RX é\\xz0", "invalid escape sequence", 14),
+        ];
+
+        for (line, reason, column) in cases {
+            expect_that!(
+                &line.parse::<Record>(),
+                eq(Err(ParseRecordError { reason, column }))
+            );
+        }
+    }
+}
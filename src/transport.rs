@@ -0,0 +1,15 @@
+//! Links to physical (and not-so-physical) supplies
+//!
+//! Anything implementing [`io::Read`](std::io::Read) and
+//! [`io::Write`](std::io::Write) can be used to talk to a supply. This module
+//! provides implementations for common cases.
+
+mod recorder;
+mod replay;
+#[cfg(feature = "serialport")]
+mod serial;
+
+pub use self::{recorder::*, replay::*};
+
+#[cfg(feature = "serialport")]
+pub use self::serial::*;
@@ -0,0 +1,155 @@
+use crate::transcript::{self, Direction, Record};
+
+use std::{io, time::Instant};
+
+/// A transport which logs everything passing through it.
+///
+/// Every chunk of bytes written to or read from the inner transport is
+/// written, with a timestamp, to a [transcript](crate::transcript). The
+/// transcript can later be decoded, or played back with [`Replay`].
+///
+/// ```
+/// use bk168xb::{sim::VirtualSupply, transport::Recorder, Supply, BK1687B};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let port = VirtualSupply::new(BK1687B);
+/// let mut supply = Supply::new(Recorder::new(port, Vec::new()), BK1687B);
+/// supply.voltage_limit()?;
+///
+/// let (_, log) = supply.into_inner().into_inner();
+/// assert!(String::from_utf8(log)?.ends_with(" RX 360\\rOK\\r\n"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Replay`]: crate::transport::Replay
+#[derive(Debug)]
+pub struct Recorder<T, W> {
+    inner: T,
+    log: W,
+    start: Instant,
+}
+
+impl<T, W> Recorder<T, W>
+where
+    W: io::Write,
+{
+    /// Wrap `inner`, logging its traffic to `log`.
+    ///
+    /// Timestamps are relative to the moment the recorder is created.
+    pub fn new(inner: T, log: W) -> Self {
+        Recorder {
+            inner,
+            log,
+            start: Instant::now(),
+        }
+    }
+
+    /// Get a reference to the underlying transport.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying transport.
+    ///
+    /// Traffic which bypasses the recorder isn't logged.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwrap the underlying transport and log.
+    pub fn into_inner(self) -> (T, W) {
+        (self.inner, self.log)
+    }
+
+    fn record(&mut self, direction: Direction, bytes: &[u8]) -> io::Result<()> {
+        let record = Record {
+            time: self.start.elapsed(),
+            direction,
+            bytes: bytes.to_vec(),
+        };
+
+        transcript::write(&mut self.log, &record)
+    }
+}
+
+impl<T, W> io::Read for Recorder<T, W>
+where
+    T: io::Read,
+    W: io::Write,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        if count > 0 {
+            self.record(Direction::Rx, &buf[..count])?;
+        }
+
+        Ok(count)
+    }
+}
+
+impl<T, W> io::Write for Recorder<T, W>
+where
+    T: io::Write,
+    W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        if count > 0 {
+            self.record(Direction::Tx, &buf[..count])?;
+        }
+
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.log.flush()
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{sim::VirtualSupply, supply::Supply, BK1687B};
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, matchers::*,
+    };
+
+    test logs_both_directions() {
+        let recorder = Recorder::new(VirtualSupply::new(BK1687B), Vec::new());
+        let mut supply = Supply::new(recorder, BK1687B);
+
+        supply.voltage_limit().unwrap();
+
+        let (_, log) = supply.into_inner().into_inner();
+        let records = transcript::read(log.as_slice()).unwrap();
+
+        let bytes = |direction| {
+            records
+                .iter()
+                .filter(|r| r.direction == direction)
+                .flat_map(|r| r.bytes.clone())
+                .collect::<Vec<_>>()
+        };
+        expect_that!(&bytes(Direction::Tx), eq(b"GOVP\r".to_vec()));
+        expect_that!(&bytes(Direction::Rx), eq(b"360\rOK\r".to_vec()));
+
+        let first_rx = records
+            .iter()
+            .position(|r| r.direction == Direction::Rx)
+            .unwrap();
+        assert_that!(
+            &records[first_rx..].iter().all(|r| r.direction == Direction::Rx),
+            eq(true)
+        );
+        assert_that!(
+            &records.windows(2).all(|pair| pair[0].time <= pair[1].time),
+            eq(true)
+        );
+    }
+}
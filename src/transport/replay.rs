@@ -0,0 +1,304 @@
+use crate::{
+    core::escape,
+    transcript::{self, Direction, Record},
+};
+
+use std::io;
+
+/// A transport which plays back a recorded session.
+///
+/// Bytes written to the replay are checked against those transmitted in the
+/// recording, and bytes read from it are those received in the recording. A
+/// received chunk only becomes available once everything transmitted before
+/// it has been written, so a replay behaves like a supply which answers
+/// exactly as the real one did.
+///
+/// This makes a session captured with a [`Recorder`] into a regression test,
+/// which runs without hardware:
+///
+/// ```
+/// use bk168xb::{transport::Replay, Supply, BK1688B};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let log = "\
+/// 0.000000 TX GOVP\\r
+/// 0.003182 RX 600\\rOK\\r
+/// ";
+///
+/// let replay = Replay::read(log.as_bytes())?;
+/// let mut supply = Supply::new(replay, BK1688B);
+///
+/// assert_eq!(supply.voltage_limit()?.0, 60.);
+/// assert!(supply.get_ref().is_finished());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Chunk boundaries in the recording don't matter; only the order of the
+/// bytes does. Once every received byte has been read, reads return `Ok(0)`,
+/// which a [`Supply`](crate::Supply) reports as
+/// [`NoResponse`](crate::response::Error::NoResponse).
+///
+/// # Panics
+///
+/// Writing bytes other than those the recording expects panics, with a
+/// message showing both. This is intended to fail a test as soon as the code
+/// under test diverges from the recorded session.
+///
+/// [`Recorder`]: crate::transport::Recorder
+#[derive(Debug, Clone)]
+pub struct Replay {
+    tx: Vec<u8>,
+    tx_pos: usize,
+    rx: Vec<Chunk>,
+    rx_pos: usize,
+}
+
+/// Bytes received during a recording.
+#[derive(Debug, Clone)]
+struct Chunk {
+    /// How many bytes had been transmitted when this chunk arrived.
+    after: usize,
+
+    bytes: Vec<u8>,
+}
+
+impl Replay {
+    /// Play back `records`.
+    pub fn new<I: IntoIterator<Item = Record>>(records: I) -> Self {
+        let mut tx = Vec::new();
+        let mut rx = Vec::new();
+
+        for record in records {
+            match record.direction {
+                Direction::Tx => tx.extend_from_slice(&record.bytes),
+                Direction::Rx => rx.push(Chunk {
+                    after: tx.len(),
+                    bytes: record.bytes,
+                }),
+            }
+        }
+        rx.retain(|chunk| !chunk.bytes.is_empty());
+
+        Replay {
+            tx,
+            tx_pos: 0,
+            rx,
+            rx_pos: 0,
+        }
+    }
+
+    /// Play back a transcript.
+    pub fn read<R: io::BufRead>(source: R) -> transcript::Result<Self> {
+        Ok(Self::new(transcript::read(source)?))
+    }
+
+    /// Whether every recorded byte has been written and read.
+    pub fn is_finished(&self) -> bool {
+        self.tx_pos == self.tx.len() && self.rx.is_empty()
+    }
+
+    /// The transmitted bytes which haven't been written yet.
+    pub fn remaining_tx(&self) -> &[u8] {
+        &self.tx[self.tx_pos..]
+    }
+}
+
+impl io::Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let chunk = match self.rx.first_mut() {
+            Some(chunk) if chunk.after <= self.tx_pos => chunk,
+            _ => return Ok(0),
+        };
+
+        let available = &chunk.bytes[self.rx_pos..];
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+
+        self.rx_pos += count;
+        if self.rx_pos == chunk.bytes.len() {
+            self.rx.remove(0);
+            self.rx_pos = 0;
+        }
+
+        Ok(count)
+    }
+}
+
+impl io::Write for Replay {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let expected = self.remaining_tx();
+        let matched = expected
+            .iter()
+            .zip(buf)
+            .take_while(|(expected, actual)| expected == actual)
+            .count();
+
+        if matched < buf.len() {
+            // Show the whole of the diverging command, not just the tail
+            // which happened to be in this write.
+            let start = self.tx[..self.tx_pos + matched]
+                .iter()
+                .rposition(|&b| b == b'\r')
+                .map_or(0, |i| i + 1);
+            let end = |bytes: &[u8]| {
+                bytes
+                    .iter()
+                    .position(|&b| b == b'\r')
+                    .map_or(bytes.len(), |i| i + 1)
+            };
+
+            let sent = &self.tx[start..self.tx_pos];
+            let mut actual = sent.to_vec();
+            actual.extend_from_slice(&buf[..end(buf)]);
+            let expected = &self.tx[start..];
+            let expected =
+                &expected[..end(&expected[sent.len()..]) + sent.len()];
+
+            panic!(
+                "replay diverged at byte {}: expected \"{}\", got \"{}\"",
+                self.tx_pos + matched,
+                escape(expected),
+                escape(&actual),
+            );
+        }
+
+        self.tx_pos += matched;
+        Ok(matched)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        response::{Error::NoResponse, Status},
+        supply::{Error, Supply},
+        sim::{Resistor, VirtualSupply},
+        transport::Recorder,
+        OutputMode, OutputState, BK1687B,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+    use std::io::{Read, Write};
+
+    fn replay(log: &str) -> Replay {
+        Replay::read(log.as_bytes()).unwrap()
+    }
+
+    test replays_recorded_session() {
+        let mut emulated = VirtualSupply::new(BK1687B);
+        emulated.set_load(Resistor(10.));
+        let recorder = Recorder::new(emulated, Vec::new());
+        let mut supply = Supply::new(recorder, BK1687B);
+        supply.set_voltage(5.).unwrap();
+        supply.set_current(1.).unwrap();
+        supply.set_output(OutputState::On).unwrap();
+        let status = supply.status().unwrap();
+        let (_, log) = supply.into_inner().into_inner();
+
+        let replay = Replay::read(log.as_slice()).unwrap();
+        let mut supply = Supply::new(replay, BK1687B);
+        supply.set_voltage(5.).unwrap();
+        supply.set_current(1.).unwrap();
+        supply.set_output(OutputState::On).unwrap();
+
+        expect_that!(&supply.status().unwrap(), eq(status));
+        expect_that!(&supply.get_ref().is_finished(), eq(true));
+    }
+
+    test ignores_chunk_boundaries() {
+        let log = "\
+            0.000000 TX GE\n\
+            0.000001 TX TD\\r\n\
+            0.000002 RX 0500\n\
+            0.000003 RX 01000\\rOK\\r\n\
+            0.000004 TX GO\n\
+            0.000004 TX VP\\r\n\
+            0.000005 RX 360\\rOK\\r\n";
+        let mut supply = Supply::new(replay(log), BK1687B);
+
+        expect_that!(&supply.status().unwrap(), eq(Status {
+            voltage: 5.,
+            current: 1.,
+            mode: OutputMode::ConstantVoltage,
+        }));
+        expect_that!(&supply.voltage_limit().unwrap().0, eq(36.));
+    }
+
+    test withholds_response_until_command_sent() {
+        let log = "0.000000 TX GETD\\r\n0.000001 RX OK\\r\n";
+        let mut replay = replay(log);
+        let mut buf = [0; 8];
+
+        expect_that!(&replay.read(&mut buf).unwrap(), eq(0));
+        replay.write_all(b"GETD\r").unwrap();
+        expect_that!(&replay.read(&mut buf).unwrap(), eq(3));
+        expect_that!(&&buf[..3], eq(&b"OK\r"[..]));
+        expect_that!(&replay.read(&mut buf).unwrap(), eq(0));
+        expect_that!(&replay.is_finished(), eq(true));
+    }
+
+    test reports_missing_response() {
+        let log = "0.000000 TX GETD\\r\n";
+        let mut supply = Supply::new(replay(log), BK1687B);
+        supply.set_timeout(std::time::Duration::ZERO);
+
+        let err = supply.status().unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(NoResponse)
+        }));
+    }
+
+    test tracks_progress() {
+        let log = "0.000000 TX GETD\\r\n0.000001 RX OK\\r\n";
+        let mut replay = replay(log);
+
+        replay.write_all(b"GET").unwrap();
+
+        expect_that!(&replay.remaining_tx(), eq(&b"D\r"[..]));
+        expect_that!(&replay.is_finished(), eq(false));
+    }
+
+    test panics_on_unexpected_command() {
+        let log = "\
+            0.000000 TX SOUT0\\r\n\
+            0.000001 RX OK\\r\n\
+            0.000002 TX VOLT050\\r\n\
+            0.000003 RX OK\\r\n";
+        let mut supply = Supply::new(replay(log), BK1687B);
+        supply.set_output(OutputState::On).unwrap();
+
+        let result = std::panic::catch_unwind(move || {
+            supply.set_voltage(5.5).unwrap();
+        });
+
+        let message = result.unwrap_err();
+        expect_that!(
+            &message.downcast_ref::<String>().unwrap().as_str(),
+            eq("replay diverged at byte 12: \
+                expected \"VOLT050\\r\", got \"VOLT055\"")
+        );
+    }
+
+    test panics_on_extra_write() {
+        let mut replay = replay("");
+
+        let result = std::panic::catch_unwind(move || {
+            replay.write_all(b"GETD\r").unwrap();
+        });
+
+        expect_that!(&result.is_err(), eq(true));
+    }
+}
@@ -0,0 +1,175 @@
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use std::{io, time::Duration};
+
+/// The supply's serial baud rate.
+///
+/// See section 1.1 of the programming manual.
+pub const BAUD_RATE: u32 = 9600;
+
+/// How long a single read from the port blocks.
+///
+/// This is deliberately much shorter than a response timeout. Read timeouts
+/// surface as [`TimedOut`](io::ErrorKind::TimedOut) errors, which a
+/// [`ResponseReader`](crate::response::ResponseReader) retries until its own
+/// deadline passes -- and only then reports
+/// [`NoResponse`](crate::response::Error::NoResponse).
+pub const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// A serial port, configured for talking to a 168xB supply.
+///
+/// The supplies present a USB virtual COM port, which expects 9600 baud, 8
+/// data bits, no parity, 1 stop bit, and no flow control.
+///
+/// ```no_run
+/// use bk168xb::{transport::SerialTransport, Supply};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let port = SerialTransport::open("/dev/ttyUSB0")?;
+/// let mut supply = Supply::detect(port)?;
+///
+/// println!("connected to a {}", supply.variant().model);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SerialTransport {
+    port: Box<dyn SerialPort>,
+}
+
+impl SerialTransport {
+    /// Open and configure the serial port at `path`.
+    pub fn open(path: &str) -> serialport::Result<Self> {
+        let port = serialport::new(path, BAUD_RATE)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .flow_control(FlowControl::None)
+            .timeout(READ_TIMEOUT)
+            .open()?;
+
+        Ok(SerialTransport { port })
+    }
+
+    /// Get a reference to the underlying port.
+    pub fn get_ref(&self) -> &dyn SerialPort {
+        &*self.port
+    }
+
+    /// Get a mutable reference to the underlying port.
+    pub fn get_mut(&mut self) -> &mut dyn SerialPort {
+        &mut *self.port
+    }
+
+    /// Unwrap the underlying port.
+    pub fn into_inner(self) -> Box<dyn SerialPort> {
+        self.port
+    }
+}
+
+impl io::Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl io::Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+#[cfg(all(test, unix))]
+galvanic_test::test_suite! {
+    name test;
+
+    use super::*;
+
+    use crate::{
+        response::Error::NoResponse,
+        supply::{Error, Supply},
+        OutputState, BK1687B,
+    };
+
+    use galvanic_assert::{
+        assert_that, expect_that, get_expectation_for, has_structure,
+        is_variant, matchers::*, structure,
+    };
+    use serialport::TTYPort;
+    use std::{
+        io::{Read, Write},
+        thread,
+    };
+
+    fixture pty() -> (TTYPort, TTYPort) {
+        setup(&mut self) {
+            let (mut master, slave) = TTYPort::pair().unwrap();
+            master.set_timeout(Duration::from_secs(1)).unwrap();
+
+            (master, slave)
+        }
+    }
+
+    test configures_port(pty) {
+        let (_master, slave) = &pty.val;
+        let path = slave.name().unwrap();
+
+        let transport = SerialTransport::open(&path).unwrap();
+        let port = transport.get_ref();
+
+        expect_that!(&port.data_bits().unwrap(), eq(DataBits::Eight));
+        expect_that!(&port.parity().unwrap(), eq(Parity::None));
+        expect_that!(&port.stop_bits().unwrap(), eq(StopBits::One));
+        expect_that!(&port.flow_control().unwrap(), eq(FlowControl::None));
+        expect_that!(&port.timeout(), eq(READ_TIMEOUT));
+    }
+
+    test talks_to_supply(pty) {
+        let (mut master, slave) = pty.val;
+        let path = slave.name().unwrap();
+
+        let remote = thread::spawn(move || {
+            let mut command = [0; 5];
+            master.read_exact(&mut command).unwrap();
+            assert_eq!(&command, b"GMAX\r");
+            master.write_all(b"3601").unwrap();
+            master.flush().unwrap();
+            thread::sleep(Duration::from_millis(10));
+            master.write_all(b"00\rOK\r").unwrap();
+
+            let mut command = [0; 6];
+            master.read_exact(&mut command).unwrap();
+            assert_eq!(&command, b"SOUT0\r");
+            master.write_all(b"OK\r").unwrap();
+
+            master
+        });
+
+        let port = SerialTransport::open(&path).unwrap();
+        let mut supply = Supply::detect(port).unwrap();
+        expect_that!(&supply.variant(), eq(BK1687B));
+        supply.set_output(OutputState::On).unwrap();
+
+        remote.join().unwrap();
+        drop(slave);
+    }
+
+    test times_out_without_response(pty) {
+        let (_master, slave) = &pty.val;
+        let path = slave.name().unwrap();
+
+        let port = SerialTransport::open(&path).unwrap();
+        let mut supply = Supply::new(port, BK1687B);
+        supply.set_timeout(Duration::from_millis(200));
+
+        let err = supply.status().unwrap_err();
+
+        assert_that!(&err, has_structure!(Error::Response {
+            source: is_variant!(NoResponse)
+        }));
+    }
+}